@@ -0,0 +1,20 @@
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+};
+use crate::Config;
+
+/// Nominated admin accepts the admin authority, completing the transfer
+pub fn process(accounts: &[AccountInfo<'_>]) -> ProgramResult {
+    let [pending_admin, config] = accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Check & Get config account data
+    let config_data = Config::get(config)?;
+
+    // Accept: Set admin to the pending admin
+    Config::accept_admin(config_data, pending_admin, config)
+}
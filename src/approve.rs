@@ -0,0 +1,28 @@
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+};
+use crate::{error::EscrowError, Escrow};
+
+/// Approver signs off on settling an escrow that requires M-of-N approvals
+pub fn process(accounts: &[AccountInfo<'_>]) -> ProgramResult {
+    let [approver, maker, escrow] = accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Make sure the approver is a signer
+    if !approver.is_signer {
+        return Err(EscrowError::MissingSigner.into());
+    }
+
+    // Check & Get escrow account data
+    let (escrow_data, _) = Escrow::get_data_and_bump(maker.key, escrow)?;
+
+    // Make sure the escrow is not frozen by a dispute
+    escrow_data.check_dispute()?;
+
+    // Approve: Record the approver's approval
+    Escrow::approve(escrow_data, approver.key, escrow)
+}
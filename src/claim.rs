@@ -0,0 +1,32 @@
+use solana_program::{
+    account_info::AccountInfo,
+    clock::Clock,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    sysvar::Sysvar,
+};
+use crate::{error::EscrowError, Claim, Escrow};
+
+/// Anyone presenting the preimage releases a hashlocked vault to the designated taker before the timeout
+pub fn process(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult {
+    let Claim { preimage } = Claim::try_from(data)?;
+
+    let [maker, mint_a, taker_ta_a, escrow, vault, token_program, _system_program, remaining_accounts @ ..] = accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Check & Get escrow account data and bump
+    let (escrow_data, bump) = Escrow::get_data_and_bump(maker.key, escrow)?;
+
+    // Make sure the escrow is not frozen by a dispute
+    escrow_data.check_dispute()?;
+
+    // Make sure the hashlock has not timed out
+    if escrow_data.is_expired(Clock::get()?.unix_timestamp) {
+        return Err(EscrowError::EscrowExpired.into());
+    }
+
+    // Claim: Transfer token A from vault to taker, Close the vault & escrow
+    Escrow::claim(escrow_data, bump, token_program.key, &preimage, mint_a, maker, escrow, vault, taker_ta_a, remaining_accounts)
+}
@@ -0,0 +1,454 @@
+//! Instruction builders for the escrow program.
+//!
+//! For Token-2022 mints with a transfer hook, append the hook's extra accounts
+//! to the returned instruction's accounts with `add_transfer_hook_accounts`.
+//!
+//! Mint A is writable wherever the vault may close, so transfer fees withheld in
+//! the vault can be harvested to the mint first.
+//!
+//! For oracle-pegged escrows, append the price feed account to Take instructions.
+//!
+//! To also record Make, Take and Refund events in inner instructions, call
+//! `add_event_accounts` before appending any transfer hook accounts.
+
+use bytemuck::bytes_of;
+use core::{
+    future::{ready, Future},
+    pin::pin,
+    task::{Context, Poll, Waker},
+};
+use solana_program::{
+    bpf_loader_upgradeable,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+};
+use spl_token_2022::{
+    extension::{transfer_hook, StateWithExtensions},
+    state::Mint,
+};
+use spl_transfer_hook_interface::offchain::{add_extra_account_metas_for_execute, AccountFetchError};
+use crate::{
+    events::EVENT_AUTHORITY_ADDRESS,
+    state::{CONFIG_ADDRESS, ESCROW_SEED, MINT_ENTRY_SEED, VAULT_SEED},
+    Claim, Deposit, EscrowInstructions, InitializeConfig, Make, Resolve, SetMintStatus, TakePartial, Update, UpdateConfig,
+    Withdraw,
+};
+
+/// Find the escrow address derived from Maker's pubkey and seed
+pub fn find_escrow_address(maker: &Pubkey, seed: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ESCROW_SEED, maker.as_ref(), seed.to_le_bytes().as_ref()], &crate::ID)
+}
+
+/// Find the vault address derived from the escrow address
+pub fn find_vault_address(escrow: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VAULT_SEED, escrow.as_ref()], &crate::ID)
+}
+
+/// Append the event authority and the program so the instruction emits its event through a self-invocation
+pub fn add_event_accounts(instruction: &mut Instruction) {
+    instruction.accounts.extend([
+        AccountMeta::new_readonly(EVENT_AUTHORITY_ADDRESS, false),
+        AccountMeta::new_readonly(crate::ID, false),
+    ]);
+}
+
+/// Append the extra accounts required by the transfer hook of `mint`, if any, for a transfer of `amount`
+/// from `source` to `destination` made by `instruction`. `get_account_data` returns the data of an account
+/// if it exists, and must at least return the hook's ExtraAccountMetaList
+#[allow(clippy::too_many_arguments)]
+pub fn add_transfer_hook_accounts(
+    instruction: &mut Instruction,
+    mint: &Pubkey,
+    mint_data: &[u8],
+    source: &Pubkey,
+    destination: &Pubkey,
+    authority: &Pubkey,
+    amount: u64,
+    get_account_data: impl Fn(&Pubkey) -> Option<Vec<u8>>,
+) -> Result<(), AccountFetchError> {
+    // Mints without a transfer hook need no extra accounts
+    let Some(program_id) = transfer_hook::get_program_id(&StateWithExtensions::<Mint>::unpack(mint_data)?) else {
+        return Ok(());
+    };
+
+    // Account data is already at hand, so resolving completes on the first poll
+    let resolve = pin!(add_extra_account_metas_for_execute(
+        instruction,
+        &program_id,
+        source,
+        mint,
+        destination,
+        authority,
+        amount,
+        |address| ready(Ok(get_account_data(&address))),
+    ));
+    match resolve.poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(result) => result,
+        Poll::Pending => unreachable!("account data is fetched synchronously"),
+    }
+}
+
+/// Find the mint registry entry address derived from the mint
+pub fn find_mint_entry_address(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[MINT_ENTRY_SEED, mint.as_ref()], &crate::ID)
+}
+
+/// Build a Make instruction depositing `make.amount` of mint A in exchange for `make.receive` of mint B
+pub fn make_instruction(
+    maker: &Pubkey,
+    mint_a: &Pubkey,
+    mint_b: &Pubkey,
+    maker_ta_a: &Pubkey,
+    token_program: &Pubkey,
+    make: Make,
+) -> Instruction {
+    let escrow = find_escrow_address(maker, make.seed).0;
+    let vault = find_vault_address(&escrow).0;
+
+    Instruction::new_with_bytes(
+        crate::ID,
+        &[&[EscrowInstructions::Make as u8], bytes_of(&make)].concat(),
+        vec![
+            AccountMeta::new(*maker, true),
+            AccountMeta::new_readonly(*mint_a, false),
+            AccountMeta::new_readonly(*mint_b, false),
+            AccountMeta::new(*maker_ta_a, false),
+            AccountMeta::new(escrow, false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(CONFIG_ADDRESS, false),
+            AccountMeta::new_readonly(find_mint_entry_address(mint_a).0, false),
+            AccountMeta::new_readonly(find_mint_entry_address(mint_b).0, false),
+        ],
+    )
+}
+
+/// Build a Take instruction paying Maker and claiming the vault of the escrow at `seed`.
+/// `treasury` receives the protocol fee, and is the treasury's token account for the fee mint unless it is native SOL
+#[allow(clippy::too_many_arguments)]
+pub fn take_instruction(
+    taker: &Pubkey,
+    maker: &Pubkey,
+    mint_a: &Pubkey,
+    mint_b: &Pubkey,
+    taker_ta_a: &Pubkey,
+    taker_ta_b: &Pubkey,
+    maker_ta_b: &Pubkey,
+    treasury: &Pubkey,
+    token_program: &Pubkey,
+    seed: u64,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        crate::ID,
+        &[EscrowInstructions::Take as u8],
+        take_accounts(taker, maker, mint_a, mint_b, taker_ta_a, taker_ta_b, maker_ta_b, treasury, token_program, seed),
+    )
+}
+
+/// Build a TakePartial instruction paying Maker pro-rata and claiming `amount` from the vault of the escrow at `seed`
+#[allow(clippy::too_many_arguments)]
+pub fn take_partial_instruction(
+    taker: &Pubkey,
+    maker: &Pubkey,
+    mint_a: &Pubkey,
+    mint_b: &Pubkey,
+    taker_ta_a: &Pubkey,
+    taker_ta_b: &Pubkey,
+    maker_ta_b: &Pubkey,
+    treasury: &Pubkey,
+    token_program: &Pubkey,
+    seed: u64,
+    amount: u64,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        crate::ID,
+        &[&[EscrowInstructions::TakePartial as u8], bytes_of(&TakePartial { amount })].concat(),
+        take_accounts(taker, maker, mint_a, mint_b, taker_ta_a, taker_ta_b, maker_ta_b, treasury, token_program, seed),
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn take_accounts(
+    taker: &Pubkey,
+    maker: &Pubkey,
+    mint_a: &Pubkey,
+    mint_b: &Pubkey,
+    taker_ta_a: &Pubkey,
+    taker_ta_b: &Pubkey,
+    maker_ta_b: &Pubkey,
+    treasury: &Pubkey,
+    token_program: &Pubkey,
+    seed: u64,
+) -> Vec<AccountMeta> {
+    let escrow = find_escrow_address(maker, seed).0;
+    let vault = find_vault_address(&escrow).0;
+
+    vec![
+        AccountMeta::new(*taker, true),
+        AccountMeta::new(*maker, false),
+        AccountMeta::new(*mint_a, false),
+        AccountMeta::new_readonly(*mint_b, false),
+        AccountMeta::new(*taker_ta_a, false),
+        AccountMeta::new(*taker_ta_b, false),
+        AccountMeta::new(*maker_ta_b, false),
+        AccountMeta::new(escrow, false),
+        AccountMeta::new(vault, false),
+        AccountMeta::new_readonly(*token_program, false),
+        AccountMeta::new_readonly(system_program::ID, false),
+        AccountMeta::new_readonly(CONFIG_ADDRESS, false),
+        AccountMeta::new(*treasury, false),
+    ]
+}
+
+/// Build a Refund instruction returning the vault of the escrow at `seed` to Maker
+pub fn refund_instruction(
+    maker: &Pubkey,
+    mint_a: &Pubkey,
+    maker_ta_a: &Pubkey,
+    token_program: &Pubkey,
+    seed: u64,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        crate::ID,
+        &[EscrowInstructions::Refund as u8],
+        refund_accounts(maker, true, mint_a, maker_ta_a, token_program, seed),
+    )
+}
+
+/// Build an Expire instruction returning the vault of the expired escrow at `seed` to Maker, callable by anyone
+pub fn expire_instruction(
+    maker: &Pubkey,
+    mint_a: &Pubkey,
+    maker_ta_a: &Pubkey,
+    token_program: &Pubkey,
+    seed: u64,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        crate::ID,
+        &[EscrowInstructions::Expire as u8],
+        refund_accounts(maker, false, mint_a, maker_ta_a, token_program, seed),
+    )
+}
+
+/// Build a Deposit instruction topping up the vault of the escrow at `seed` with `amount` of mint A
+pub fn deposit_instruction(
+    maker: &Pubkey,
+    mint_a: &Pubkey,
+    maker_ta_a: &Pubkey,
+    token_program: &Pubkey,
+    seed: u64,
+    amount: u64,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        crate::ID,
+        &[&[EscrowInstructions::Deposit as u8], bytes_of(&Deposit { amount })].concat(),
+        refund_accounts(maker, true, mint_a, maker_ta_a, token_program, seed),
+    )
+}
+
+/// Build a Withdraw instruction returning `amount` from the vault of the escrow at `seed` to Maker
+pub fn withdraw_instruction(
+    maker: &Pubkey,
+    mint_a: &Pubkey,
+    maker_ta_a: &Pubkey,
+    token_program: &Pubkey,
+    seed: u64,
+    amount: u64,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        crate::ID,
+        &[&[EscrowInstructions::Withdraw as u8], bytes_of(&Withdraw { amount })].concat(),
+        refund_accounts(maker, true, mint_a, maker_ta_a, token_program, seed),
+    )
+}
+
+/// Build a Claim instruction releasing the vault of the hashlocked escrow at `seed` to the designated taker's `taker_ta_a`, callable by anyone holding the preimage
+pub fn claim_instruction(
+    maker: &Pubkey,
+    mint_a: &Pubkey,
+    taker_ta_a: &Pubkey,
+    token_program: &Pubkey,
+    seed: u64,
+    preimage: [u8; 32],
+) -> Instruction {
+    Instruction::new_with_bytes(
+        crate::ID,
+        &[&[EscrowInstructions::Claim as u8], bytes_of(&Claim { preimage })].concat(),
+        refund_accounts(maker, false, mint_a, taker_ta_a, token_program, seed),
+    )
+}
+
+fn refund_accounts(
+    maker: &Pubkey,
+    maker_is_signer: bool,
+    mint_a: &Pubkey,
+    maker_ta_a: &Pubkey,
+    token_program: &Pubkey,
+    seed: u64,
+) -> Vec<AccountMeta> {
+    let escrow = find_escrow_address(maker, seed).0;
+    let vault = find_vault_address(&escrow).0;
+
+    vec![
+        AccountMeta::new(*maker, maker_is_signer),
+        AccountMeta::new(*mint_a, false),
+        AccountMeta::new(*maker_ta_a, false),
+        AccountMeta::new(escrow, false),
+        AccountMeta::new(vault, false),
+        AccountMeta::new_readonly(*token_program, false),
+        AccountMeta::new_readonly(system_program::ID, false),
+    ]
+}
+
+/// Build a Migrate instruction upgrading the escrow at `seed` to the current layout, with `payer` covering the extra rent
+pub fn migrate_instruction(payer: &Pubkey, maker: &Pubkey, seed: u64) -> Instruction {
+    let escrow = find_escrow_address(maker, seed).0;
+
+    Instruction::new_with_bytes(
+        crate::ID,
+        &[EscrowInstructions::Migrate as u8],
+        vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(*maker, false),
+            AccountMeta::new(escrow, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+    )
+}
+
+/// Build an Update instruction repricing the escrow at `seed` to `update.receive` of `mint_b`
+pub fn update_instruction(maker: &Pubkey, mint_b: &Pubkey, seed: u64, update: Update) -> Instruction {
+    let escrow = find_escrow_address(maker, seed).0;
+
+    Instruction::new_with_bytes(
+        crate::ID,
+        &[&[EscrowInstructions::Update as u8], bytes_of(&update)].concat(),
+        vec![
+            AccountMeta::new_readonly(*maker, true),
+            AccountMeta::new_readonly(*mint_b, false),
+            AccountMeta::new(escrow, false),
+            AccountMeta::new_readonly(CONFIG_ADDRESS, false),
+            AccountMeta::new_readonly(find_mint_entry_address(mint_b).0, false),
+        ],
+    )
+}
+
+/// Build an InitializeConfig instruction creating the global config, signed by the program's upgrade authority
+pub fn initialize_config_instruction(authority: &Pubkey, initialize_config: InitializeConfig) -> Instruction {
+    let program_data = Pubkey::find_program_address(&[crate::ID.as_ref()], &bpf_loader_upgradeable::ID).0;
+
+    Instruction::new_with_bytes(
+        crate::ID,
+        &[&[EscrowInstructions::InitializeConfig as u8], bytes_of(&initialize_config)].concat(),
+        vec![
+            AccountMeta::new(*authority, true),
+            AccountMeta::new(CONFIG_ADDRESS, false),
+            AccountMeta::new_readonly(program_data, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+    )
+}
+
+/// Build an UpdateConfig instruction replacing the config settings, signed by the admin.
+/// A non-default `update_config.pending_admin` nominates a new admin, who takes over with AcceptAdmin
+pub fn update_config_instruction(admin: &Pubkey, update_config: UpdateConfig) -> Instruction {
+    Instruction::new_with_bytes(
+        crate::ID,
+        &[&[EscrowInstructions::UpdateConfig as u8], bytes_of(&update_config)].concat(),
+        vec![
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new(CONFIG_ADDRESS, false),
+        ],
+    )
+}
+
+/// Build an AcceptAdmin instruction completing an admin transfer, signed by the nominated admin
+pub fn accept_admin_instruction(pending_admin: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        crate::ID,
+        &[EscrowInstructions::AcceptAdmin as u8],
+        vec![
+            AccountMeta::new_readonly(*pending_admin, true),
+            AccountMeta::new(CONFIG_ADDRESS, false),
+        ],
+    )
+}
+
+/// Build a SetMintStatus instruction allowing, denying or unlisting `mint` in the registry, signed by the admin
+pub fn set_mint_status_instruction(admin: &Pubkey, mint: &Pubkey, status: u8) -> Instruction {
+    Instruction::new_with_bytes(
+        crate::ID,
+        &[&[EscrowInstructions::SetMintStatus as u8], bytes_of(&SetMintStatus { status })].concat(),
+        vec![
+            AccountMeta::new(*admin, true),
+            AccountMeta::new_readonly(CONFIG_ADDRESS, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(find_mint_entry_address(mint).0, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+    )
+}
+
+/// Build an Approve instruction recording `approver`'s sign-off on settling the escrow at `seed`
+pub fn approve_instruction(approver: &Pubkey, maker: &Pubkey, seed: u64) -> Instruction {
+    let escrow = find_escrow_address(maker, seed).0;
+
+    Instruction::new_with_bytes(
+        crate::ID,
+        &[EscrowInstructions::Approve as u8],
+        vec![
+            AccountMeta::new_readonly(*approver, true),
+            AccountMeta::new_readonly(*maker, false),
+            AccountMeta::new(escrow, false),
+        ],
+    )
+}
+
+/// Build a Dispute instruction freezing the escrow at `seed`, signed by the maker or the taker
+pub fn dispute_instruction(party: &Pubkey, maker: &Pubkey, seed: u64) -> Instruction {
+    let escrow = find_escrow_address(maker, seed).0;
+
+    Instruction::new_with_bytes(
+        crate::ID,
+        &[EscrowInstructions::Dispute as u8],
+        vec![
+            AccountMeta::new_readonly(*party, true),
+            AccountMeta::new_readonly(*maker, false),
+            AccountMeta::new(escrow, false),
+        ],
+    )
+}
+
+/// Build a Resolve instruction splitting the vault of the disputed escrow at `seed`, signed by the arbiter
+#[allow(clippy::too_many_arguments)]
+pub fn resolve_instruction(
+    arbiter: &Pubkey,
+    maker: &Pubkey,
+    mint_a: &Pubkey,
+    maker_ta_a: &Pubkey,
+    taker_ta_a: &Pubkey,
+    token_program: &Pubkey,
+    seed: u64,
+    resolve: Resolve,
+) -> Instruction {
+    let escrow = find_escrow_address(maker, seed).0;
+    let vault = find_vault_address(&escrow).0;
+
+    Instruction::new_with_bytes(
+        crate::ID,
+        &[&[EscrowInstructions::Resolve as u8], bytes_of(&resolve)].concat(),
+        vec![
+            AccountMeta::new_readonly(*arbiter, true),
+            AccountMeta::new(*maker, false),
+            AccountMeta::new(*mint_a, false),
+            AccountMeta::new(*maker_ta_a, false),
+            AccountMeta::new(*taker_ta_a, false),
+            AccountMeta::new(escrow, false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+    )
+}
@@ -0,0 +1,38 @@
+use solana_program::{
+    account_info::AccountInfo,
+    clock::Clock,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    sysvar::Sysvar,
+};
+use crate::{error::EscrowError, Deposit, Escrow};
+
+/// Maker tops up the vault of an open escrow
+pub fn process(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult {
+    let Deposit { amount } = Deposit::try_from(data)?;
+
+    let [maker, mint_a, maker_ta_a, escrow, vault, token_program, _system_program, remaining_accounts @ ..] =
+        accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Make sure the maker is a signer
+    if !maker.is_signer {
+        return Err(EscrowError::MissingSigner.into());
+    }
+
+    // Check & Get escrow account data
+    let (escrow_data, _) = Escrow::get_data_and_bump(maker.key, escrow)?;
+
+    // Make sure the escrow is not frozen by a dispute
+    escrow_data.check_dispute()?;
+
+    // Make sure the escrow has not expired
+    if escrow_data.is_expired(Clock::get()?.unix_timestamp) {
+        return Err(EscrowError::EscrowExpired.into());
+    }
+
+    // Top up: Transfer funds into the vault, Scale receive if priced proportionally
+    Escrow::top_up(escrow_data, token_program.key, amount, mint_a, maker, maker_ta_a, escrow, vault, remaining_accounts)
+}
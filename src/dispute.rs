@@ -0,0 +1,28 @@
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+};
+use crate::{error::EscrowError, Escrow};
+
+/// Maker or taker freezes an escrow until its arbiter resolves it
+pub fn process(accounts: &[AccountInfo<'_>]) -> ProgramResult {
+    let [party, maker, escrow] = accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Make sure the party is a signer
+    if !party.is_signer {
+        return Err(EscrowError::MissingSigner.into());
+    }
+
+    // Check & Get escrow account data
+    let (escrow_data, _) = Escrow::get_data_and_bump(maker.key, escrow)?;
+
+    // Make sure the escrow is not already disputed
+    escrow_data.check_dispute()?;
+
+    // Dispute: Freeze the escrow
+    Escrow::dispute(escrow_data, party, escrow)
+}
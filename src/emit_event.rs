@@ -0,0 +1,21 @@
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+};
+use crate::{error::EscrowError, events::EVENT_AUTHORITY_ADDRESS};
+
+/// No-op invoked by the program itself so indexers can read events from inner instructions
+pub fn process(accounts: &[AccountInfo<'_>]) -> ProgramResult {
+    let [event_authority, ..] = accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Make sure only the program, signing as the event authority, can emit events
+    if event_authority.key.ne(&EVENT_AUTHORITY_ADDRESS) || !event_authority.is_signer {
+        return Err(EscrowError::InvalidAuthority.into());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,141 @@
+use core::fmt;
+use solana_program::{msg, program_error::ProgramError};
+
+/// Errors returned by the escrow program as `ProgramError::Custom` codes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EscrowError {
+    /// Mint account does not match the mint stored in the escrow
+    MintMismatch,
+    /// Vault is not the PDA derived from the escrow address
+    InvalidVaultAddress,
+    /// Escrow is not the PDA derived from the maker and seed
+    InvalidEscrowAddress,
+    /// Token program is neither SPL Token nor Token-2022
+    UnsupportedTokenProgram,
+    /// Vault token account is not owned by the escrow
+    InvalidVaultOwner,
+    /// A required signature is missing
+    MissingSigner,
+    /// Fill amount is zero or exceeds the vault balance
+    InvalidFillAmount,
+    /// Expiry timestamp is not in the future
+    InvalidExpiry,
+    /// Escrow has expired and can no longer be taken
+    EscrowExpired,
+    /// Escrow has not expired yet
+    EscrowNotExpired,
+    /// Token account is not owned by the maker
+    InvalidMakerTokenAccount,
+    /// Signer is not the designated taker of a private escrow
+    InvalidTaker,
+    /// Escrow flags contain unknown bits
+    InvalidFlags,
+    /// Account is not owned by the escrow program
+    InvalidAccountOwner,
+    /// Account discriminator does not match the expected account kind
+    InvalidDiscriminator,
+    /// Account layout version is not supported
+    UnsupportedVersion,
+    /// Escrow uses an older layout and must be migrated first
+    MigrationRequired,
+    /// Escrow already uses the current layout
+    AlreadyMigrated,
+    /// Deposit or withdrawal amount is zero or would empty the vault
+    InvalidAmount,
+    /// Config is not the PDA derived from the config seed
+    InvalidConfigAddress,
+    /// Signer is not the authority allowed to perform this action
+    InvalidAuthority,
+    /// Fee exceeds 10,000 basis points
+    InvalidFee,
+    /// Treasury account is not owned by the configured treasury
+    InvalidTreasury,
+    /// Make and Take are paused by the config admin
+    ProtocolPaused,
+    /// Mint entry is not the PDA derived from the mint
+    InvalidMintEntryAddress,
+    /// Mint status is not unlisted, allowed or denied
+    InvalidMintStatus,
+    /// Mint is not allowed by the registry
+    MintNotAllowed,
+    /// Auction ends before it starts or its price rises
+    InvalidAuction,
+    /// Price feed is missing, malformed or its peg settings are invalid
+    InvalidPriceFeed,
+    /// Price feed was published longer ago than the staleness bound
+    StalePrice,
+    /// Hashlock is set without a designated taker and a timeout
+    InvalidHashlock,
+    /// Escrow is not hashlocked or the preimage does not match the hashlock
+    InvalidPreimage,
+    /// Instruction is not allowed on a hashlocked escrow
+    HashlockedEscrow,
+    /// Approval threshold exceeds the number of approvers
+    InvalidThreshold,
+    /// Signer is not an approver of the escrow
+    InvalidApprover,
+    /// Escrow has fewer approvals than its threshold
+    ApprovalsPending,
+    /// Arbiter is set without a designated taker, or missing for a dispute
+    InvalidArbiter,
+    /// Escrow is frozen by a dispute
+    EscrowDisputed,
+    /// Escrow is not disputed
+    EscrowNotDisputed,
+    /// Maker cannot act alone on an escrow with an arbiter before it expires
+    ArbitratedEscrow,
+}
+
+impl fmt::Display for EscrowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::MintMismatch => "Mint does not match escrow",
+            Self::InvalidVaultAddress => "Invalid vault address",
+            Self::InvalidEscrowAddress => "Invalid escrow address",
+            Self::UnsupportedTokenProgram => "Unsupported token program",
+            Self::InvalidVaultOwner => "Vault is not owned by escrow",
+            Self::MissingSigner => "Missing required signature",
+            Self::InvalidFillAmount => "Invalid fill amount",
+            Self::InvalidExpiry => "Invalid expiry",
+            Self::EscrowExpired => "Escrow has expired",
+            Self::EscrowNotExpired => "Escrow has not expired",
+            Self::InvalidMakerTokenAccount => "Token account is not owned by maker",
+            Self::InvalidTaker => "Signer is not the designated taker",
+            Self::InvalidFlags => "Invalid escrow flags",
+            Self::InvalidAccountOwner => "Account is not owned by escrow program",
+            Self::InvalidDiscriminator => "Invalid account discriminator",
+            Self::UnsupportedVersion => "Unsupported account version",
+            Self::MigrationRequired => "Escrow must be migrated first",
+            Self::AlreadyMigrated => "Escrow is already migrated",
+            Self::InvalidAmount => "Invalid deposit or withdrawal amount",
+            Self::InvalidConfigAddress => "Invalid config address",
+            Self::InvalidAuthority => "Signer is not the authority",
+            Self::InvalidFee => "Invalid fee",
+            Self::InvalidTreasury => "Treasury is not owned by configured treasury",
+            Self::ProtocolPaused => "Protocol is paused",
+            Self::InvalidMintEntryAddress => "Invalid mint entry address",
+            Self::InvalidMintStatus => "Invalid mint status",
+            Self::MintNotAllowed => "Mint is not allowed",
+            Self::InvalidAuction => "Invalid auction",
+            Self::InvalidPriceFeed => "Invalid price feed",
+            Self::StalePrice => "Price feed is stale",
+            Self::InvalidHashlock => "Invalid hashlock",
+            Self::InvalidPreimage => "Invalid preimage",
+            Self::HashlockedEscrow => "Escrow is hashlocked",
+            Self::InvalidThreshold => "Invalid approval threshold",
+            Self::InvalidApprover => "Signer is not an approver",
+            Self::ApprovalsPending => "Escrow is awaiting approvals",
+            Self::InvalidArbiter => "Invalid arbiter",
+            Self::EscrowDisputed => "Escrow is disputed",
+            Self::EscrowNotDisputed => "Escrow is not disputed",
+            Self::ArbitratedEscrow => "Escrow has an arbiter",
+        })
+    }
+}
+
+impl From<EscrowError> for ProgramError {
+    fn from(e: EscrowError) -> Self {
+        msg!("Error: {}", e);
+        ProgramError::Custom(e as u32)
+    }
+}
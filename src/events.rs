@@ -0,0 +1,104 @@
+//! Events logged by the escrow program with `sol_log_data`.
+//!
+//! Each event is a single `Program data:` log entry holding the base64 of an
+//! `EscrowEvent`. Indexers decode the entry and pass it to `EscrowEvent::decode`.
+//!
+//! Logs can be truncated, so callers may also pass `EVENT_AUTHORITY_ADDRESS` and
+//! the program as the first remaining accounts. The program then invokes itself
+//! with an `EmitEvent` instruction carrying the same bytes after its
+//! discriminator, readable from the transaction's inner instructions.
+
+use bytemuck::{bytes_of, Pod, Zeroable};
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    log::sol_log_data,
+    program::invoke_signed,
+    pubkey,
+    pubkey::Pubkey,
+};
+use crate::EscrowInstructions;
+
+/// Event authority PDA derived from `[b"event_authority"]`, the only signer accepted by EmitEvent
+pub const EVENT_AUTHORITY_ADDRESS: Pubkey = pubkey!("AcZyMRRFqb1NkGifrgzTGTkcDXkQ1rDpyPaSe26twPza");
+
+/// Bump of `EVENT_AUTHORITY_ADDRESS`
+pub const EVENT_AUTHORITY_BUMP: u8 = 254;
+
+/// Seed of the event authority PDA
+pub const EVENT_AUTHORITY_SEED: &[u8] = b"event_authority";
+
+/// Current event layout version
+pub const EVENT_VERSION: u8 = 1;
+
+/// Maker opened an escrow, `amount_a` deposited for `amount_b` asked
+pub const EVENT_MAKE: u8 = 0;
+
+/// Taker filled an escrow, `amount_a` claimed for `amount_b` paid, before protocol fees
+pub const EVENT_TAKE: u8 = 1;
+
+/// Maker was refunded `amount_a` and the escrow closed
+pub const EVENT_REFUND: u8 = 2;
+
+/// Taker claimed `amount_a` of a hashlocked escrow with its preimage
+pub const EVENT_CLAIM: u8 = 3;
+
+/// Arbiter released `amount_a` to the taker, logged alongside `EVENT_RESOLVE_MAKER`
+pub const EVENT_RESOLVE: u8 = 4;
+
+/// Arbiter returned `amount_a` to the maker, logged alongside `EVENT_RESOLVE`
+pub const EVENT_RESOLVE_MAKER: u8 = 5;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Pod, Zeroable)]
+pub struct EscrowEvent {
+    pub version: u8,
+    pub kind: u8,
+    pub _padding: [u8; 6],
+    pub seed: u64,
+    pub escrow: Pubkey,
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub amount_a: u64,
+    pub amount_b: u64,
+    pub fee_a: u64,
+    pub fee_b: u64,
+}
+
+impl EscrowEvent {
+    #[inline]
+    pub fn emit<'a>(&self, remaining_accounts: &[AccountInfo<'a>]) -> ProgramResult {
+        // Log the event
+        sol_log_data(&[bytes_of(self)]);
+
+        // Record the event in an inner instruction too, if the caller opted in
+        let [event_authority, program, ..] = remaining_accounts else {
+            return Ok(());
+        };
+        if event_authority.key.ne(&EVENT_AUTHORITY_ADDRESS) || program.key.ne(&crate::ID) {
+            return Ok(());
+        }
+
+        invoke_signed(
+            &Instruction::new_with_bytes(
+                crate::ID,
+                &[&[EscrowInstructions::EmitEvent as u8], bytes_of(self)].concat(),
+                vec![AccountMeta::new_readonly(EVENT_AUTHORITY_ADDRESS, true)],
+            ),
+            &[
+                event_authority.clone(),
+                program.clone(),
+            ],
+            &[&[EVENT_AUTHORITY_SEED, &[EVENT_AUTHORITY_BUMP]]],
+        )
+    }
+
+    /// Decode an event from log data, returning `None` for other programs' data or unknown versions
+    pub fn decode(data: &[u8]) -> Option<EscrowEvent> {
+        let event = bytemuck::try_pod_read_unaligned::<EscrowEvent>(data).ok()?;
+        (event.version == EVENT_VERSION).then_some(event)
+    }
+}
@@ -0,0 +1,35 @@
+use solana_program::{
+    account_info::AccountInfo,
+    clock::Clock,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    sysvar::Sysvar,
+};
+use crate::{error::EscrowError, utils::get_token_account_owner, Escrow};
+
+/// Permissionlessly refund funds in vault of an expired escrow to Maker's token account
+pub fn process(accounts: &[AccountInfo<'_>]) -> ProgramResult {
+    let [maker, mint_a, maker_ta_a, escrow, vault, token_program, _system_program, remaining_accounts @ ..] = accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Check & Get escrow account data and bump
+    let (escrow_data, bump) = Escrow::get_data_and_bump(maker.key, escrow)?;
+
+    // Make sure the escrow is not frozen by a dispute
+    escrow_data.check_dispute()?;
+
+    // Make sure the escrow has expired
+    if !escrow_data.is_expired(Clock::get()?.unix_timestamp) {
+        return Err(EscrowError::EscrowNotExpired.into());
+    }
+
+    // Make sure the funds are returned to the maker, as anyone can call this
+    if !escrow_data.is_native_a() && maker.key.ne(&get_token_account_owner(maker_ta_a)?) {
+        return Err(EscrowError::InvalidMakerTokenAccount.into());
+    }
+
+    // Refund: Transfer token A from vault to maker, Close the vault & escrow
+    Escrow::refund(escrow_data, bump, token_program.key, mint_a, maker, escrow, vault, maker_ta_a, remaining_accounts)
+}
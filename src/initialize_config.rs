@@ -0,0 +1,43 @@
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+};
+use crate::{error::EscrowError, utils::check_upgrade_authority, Config, InitializeConfig};
+
+/// Upgrade authority creates the global config holding the protocol fee, treasury and admin
+pub fn process(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult {
+    let InitializeConfig {
+        fee_bps,
+        flags,
+        treasury,
+        admin,
+        ..
+    } = InitializeConfig::try_from(data)?;
+
+    let [authority, config, program_data, _system_program] = accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Make sure the authority is a signer
+    if !authority.is_signer {
+        return Err(EscrowError::MissingSigner.into());
+    }
+
+    // Check the authority is the program's upgrade authority
+    check_upgrade_authority(program_data, authority.key)?;
+
+    // Initialize config account & data
+    Config::init(
+        Config {
+            fee_bps,
+            flags,
+            treasury,
+            admin,
+            ..Default::default()
+        },
+        authority,
+        config,
+    )
+}
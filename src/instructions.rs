@@ -1,30 +1,87 @@
 use bytemuck::{Pod, Zeroable};
-use solana_program::program_error::ProgramError;
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use crate::state::MAX_APPROVERS;
+
+/// Instruction discriminators, the first byte of every instruction
+#[repr(u8)]
+#[derive(Clone, Copy)]
 pub enum EscrowInstructions {
-    Make,
-    Take,
-    Refund,
+    Make = 0,
+    Take = 1,
+    Refund = 2,
+    TakePartial = 3,
+    Expire = 4,
+    Migrate = 5,
+    Update = 6,
+    Deposit = 7,
+    Withdraw = 8,
+    InitializeConfig = 9,
+    UpdateConfig = 10,
+    AcceptAdmin = 11,
+    SetMintStatus = 12,
+    EmitEvent = 13,
+    Claim = 14,
+    Approve = 15,
+    Dispute = 16,
+    Resolve = 17,
+}
+
+impl EscrowInstructions {
+    const ALL: [Self; 18] = [
+        Self::Make,
+        Self::Take,
+        Self::Refund,
+        Self::TakePartial,
+        Self::Expire,
+        Self::Migrate,
+        Self::Update,
+        Self::Deposit,
+        Self::Withdraw,
+        Self::InitializeConfig,
+        Self::UpdateConfig,
+        Self::AcceptAdmin,
+        Self::SetMintStatus,
+        Self::EmitEvent,
+        Self::Claim,
+        Self::Approve,
+        Self::Dispute,
+        Self::Resolve,
+    ];
 }
 
 impl TryFrom<&u8> for EscrowInstructions {
     type Error = ProgramError;
 
     fn try_from(value: &u8) -> Result<Self, Self::Error> {
-        match value {
-            0 => Ok(Self::Make),
-            1 => Ok(Self::Take),
-            2 => Ok(Self::Refund),
-            _ => Err(ProgramError::InvalidInstructionData),
-        }
+        Self::ALL
+            .into_iter()
+            .find(|&instruction| instruction as u8 == *value)
+            .ok_or(ProgramError::InvalidInstructionData)
     }
 }
 
 #[repr(C)]
-#[derive(Clone, Copy, PartialEq, Eq, Pod, Zeroable)]
+#[derive(Clone, Copy, Default, PartialEq, Eq, Pod, Zeroable)]
 pub struct Make {
     pub seed: u64,
     pub amount: u64,
     pub receive: u64,
+    pub expires_at: i64,
+    pub taker: Pubkey,
+    pub flags: u8,
+    pub _padding: [u8; 7],
+    pub auction_start: i64,
+    pub auction_end: i64,
+    pub auction_end_receive: u64,
+    pub price_feed: Pubkey,
+    pub max_staleness: i64,
+    pub premium_bps: i16,
+    pub _padding_2: [u8; 6],
+    pub hashlock: [u8; 32],
+    pub approvers: [Pubkey; MAX_APPROVERS],
+    pub threshold: u8,
+    pub _padding_3: [u8; 7],
+    pub arbiter: Pubkey,
 }
 
 impl TryFrom<&[u8]> for Make {
@@ -36,3 +93,162 @@ impl TryFrom<&[u8]> for Make {
             .map_err(|_| ProgramError::InvalidInstructionData)
     }
 }
+
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, Pod, Zeroable)]
+pub struct TakePartial {
+    pub amount: u64,
+}
+
+impl TryFrom<&[u8]> for TakePartial {
+
+    type Error = ProgramError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        bytemuck::try_pod_read_unaligned::<Self>(data)
+            .map_err(|_| ProgramError::InvalidInstructionData)
+    }
+}
+
+/// Update replaces the expiry with `expires_at`, zero removing it, instead of leaving it untouched
+pub const UPDATE_FLAG_SET_EXPIRY: u8 = 1 << 0;
+
+#[repr(C)]
+#[derive(Clone, Copy, Default, PartialEq, Eq, Pod, Zeroable)]
+pub struct Update {
+    pub receive: u64,
+    pub expires_at: i64,
+    pub flags: u8,
+    pub _padding: [u8; 7],
+}
+
+impl TryFrom<&[u8]> for Update {
+
+    type Error = ProgramError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        bytemuck::try_pod_read_unaligned::<Self>(data)
+            .map_err(|_| ProgramError::InvalidInstructionData)
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, Pod, Zeroable)]
+pub struct Deposit {
+    pub amount: u64,
+}
+
+impl TryFrom<&[u8]> for Deposit {
+
+    type Error = ProgramError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        bytemuck::try_pod_read_unaligned::<Self>(data)
+            .map_err(|_| ProgramError::InvalidInstructionData)
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, Pod, Zeroable)]
+pub struct Withdraw {
+    pub amount: u64,
+}
+
+impl TryFrom<&[u8]> for Withdraw {
+
+    type Error = ProgramError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        bytemuck::try_pod_read_unaligned::<Self>(data)
+            .map_err(|_| ProgramError::InvalidInstructionData)
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default, PartialEq, Eq, Pod, Zeroable)]
+pub struct InitializeConfig {
+    pub fee_bps: u16,
+    pub flags: u8,
+    pub _padding: u8,
+    pub treasury: Pubkey,
+    pub admin: Pubkey,
+}
+
+impl TryFrom<&[u8]> for InitializeConfig {
+
+    type Error = ProgramError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        bytemuck::try_pod_read_unaligned::<Self>(data)
+            .map_err(|_| ProgramError::InvalidInstructionData)
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default, PartialEq, Eq, Pod, Zeroable)]
+pub struct UpdateConfig {
+    pub fee_bps: u16,
+    pub flags: u8,
+    pub _padding: u8,
+    pub treasury: Pubkey,
+    pub pending_admin: Pubkey,
+}
+
+impl TryFrom<&[u8]> for UpdateConfig {
+
+    type Error = ProgramError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        bytemuck::try_pod_read_unaligned::<Self>(data)
+            .map_err(|_| ProgramError::InvalidInstructionData)
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, Pod, Zeroable)]
+pub struct SetMintStatus {
+    pub status: u8,
+}
+
+impl TryFrom<&[u8]> for SetMintStatus {
+
+    type Error = ProgramError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        bytemuck::try_pod_read_unaligned::<Self>(data)
+            .map_err(|_| ProgramError::InvalidInstructionData)
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, Pod, Zeroable)]
+pub struct Claim {
+    pub preimage: [u8; 32],
+}
+
+impl TryFrom<&[u8]> for Claim {
+
+    type Error = ProgramError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        bytemuck::try_pod_read_unaligned::<Self>(data)
+            .map_err(|_| ProgramError::InvalidInstructionData)
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, Pod, Zeroable)]
+pub struct Resolve {
+    pub maker_amount: u64,
+    pub taker_amount: u64,
+}
+
+impl TryFrom<&[u8]> for Resolve {
+
+    type Error = ProgramError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        bytemuck::try_pod_read_unaligned::<Self>(data)
+            .map_err(|_| ProgramError::InvalidInstructionData)
+    }
+}
@@ -1,23 +1,44 @@
-mod instructions;
+pub mod instructions;
 use instructions::*;
 
-mod state;
+pub mod error;
+
+pub mod events;
+
+pub mod state;
 use state::*;
 
 #[cfg(test)]
 mod tests;
 
+#[cfg(all(any(feature = "client", test), not(target_os = "solana")))]
+pub mod client;
+
+mod accept_admin;
+mod approve;
+mod claim;
+mod deposit;
+mod dispute;
+mod emit_event;
+mod expire;
+mod initialize_config;
 mod make;
+mod migrate;
 mod refund;
+mod resolve;
+mod set_mint_status;
 mod take;
+mod update;
+mod update_config;
 mod utils;
+mod withdraw;
 
 use solana_program::{
     account_info::AccountInfo, entrypoint, entrypoint::ProgramResult, program_error::ProgramError,
     pubkey, pubkey::Pubkey,
 };
 
-const ID: Pubkey = pubkey!("2oXupQcZBcNtq5H1SjzdAZ2eKv1AxiE6XbLk4Ancw2bB");
+pub const ID: Pubkey = pubkey!("2oXupQcZBcNtq5H1SjzdAZ2eKv1AxiE6XbLk4Ancw2bB");
 
 entrypoint!(process_instruction);
 
@@ -38,5 +59,20 @@ pub fn process_instruction(
         EscrowInstructions::Make => make::process(accounts, data),
         EscrowInstructions::Take => take::process(accounts),
         EscrowInstructions::Refund => refund::process(accounts),
+        EscrowInstructions::TakePartial => take::process_partial(accounts, data),
+        EscrowInstructions::Expire => expire::process(accounts),
+        EscrowInstructions::Migrate => migrate::process(accounts),
+        EscrowInstructions::Update => update::process(accounts, data),
+        EscrowInstructions::Deposit => deposit::process(accounts, data),
+        EscrowInstructions::Withdraw => withdraw::process(accounts, data),
+        EscrowInstructions::InitializeConfig => initialize_config::process(accounts, data),
+        EscrowInstructions::UpdateConfig => update_config::process(accounts, data),
+        EscrowInstructions::AcceptAdmin => accept_admin::process(accounts),
+        EscrowInstructions::SetMintStatus => set_mint_status::process(accounts, data),
+        EscrowInstructions::EmitEvent => emit_event::process(accounts),
+        EscrowInstructions::Claim => claim::process(accounts, data),
+        EscrowInstructions::Approve => approve::process(accounts),
+        EscrowInstructions::Dispute => dispute::process(accounts),
+        EscrowInstructions::Resolve => resolve::process(accounts, data),
     }
 }
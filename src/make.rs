@@ -3,7 +3,7 @@ use solana_program::{
     entrypoint::ProgramResult,
     program_error::ProgramError,
 };
-use crate::{Escrow, Make};
+use crate::{error::EscrowError, events::EVENT_MAKE, Config, Escrow, Make, MintEntry};
 
 /// Deposit funds into vault derived from Makers's pubkey and seed
 pub fn process(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult {
@@ -11,17 +11,79 @@ pub fn process(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult {
         seed,
         amount,
         receive,
+        expires_at,
+        taker,
+        flags,
+        auction_start,
+        auction_end,
+        auction_end_receive,
+        price_feed,
+        max_staleness,
+        premium_bps,
+        hashlock,
+        approvers,
+        threshold,
+        arbiter,
+        ..
     } = Make::try_from(data)?;
 
-    let [maker, mint_a, mint_b, maker_ta_a, escrow, vault, token_program, _system_program] =
+    let [maker, mint_a, mint_b, maker_ta_a, escrow, vault, token_program, _system_program, config, mint_a_entry, mint_b_entry, remaining_accounts @ ..] =
         accounts
     else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
 
-    // Initialize escrow account & data
-    Escrow::init(seed, receive, *mint_a.key, *mint_b.key, &maker, &escrow)?;
+    // Get protocol settings, if configured
+    let config_data = Config::load(config)?;
 
-    // Deposit funds into vault
-    Escrow::deposit(escrow.key, token_program.key, amount, &maker_ta_a, &mint_a, &vault, &maker)
+    // Make sure trading is not paused
+    if config_data.is_some_and(|config_data| config_data.is_paused()) {
+        return Err(EscrowError::ProtocolPaused.into());
+    }
+
+    // Make sure both mints may be listed
+    MintEntry::check_listable(config_data, mint_a.key, mint_a_entry)?;
+    MintEntry::check_listable(config_data, mint_b.key, mint_b_entry)?;
+
+    let mut escrow_data = Escrow {
+        seed,
+        maker: *maker.key,
+        mint_a: *mint_a.key,
+        mint_b: *mint_b.key,
+        receive,
+        expires_at,
+        taker,
+        flags,
+        auction_start,
+        auction_end,
+        auction_end_receive,
+        price_feed,
+        max_staleness,
+        premium_bps,
+        hashlock,
+        approvers,
+        threshold,
+        arbiter,
+        ..Default::default()
+    };
+
+    let escrow_data = if escrow_data.is_native_a() {
+        // Deposit SOL directly into the escrow
+        let escrow_data = Escrow::init(escrow_data, maker, escrow)?;
+        Escrow::deposit_native(amount, escrow, maker)?;
+        escrow_data
+    } else {
+        // Create & initialize the vault token account
+        escrow_data.vault_bump = Escrow::init_vault(escrow.key, token_program.key, mint_a, vault, maker)?;
+
+        // Initialize escrow account & data
+        let escrow_data = Escrow::init(escrow_data, maker, escrow)?;
+
+        // Deposit funds into vault
+        Escrow::deposit(&escrow_data, escrow.key, token_program.key, amount, maker_ta_a, mint_a, vault, maker, remaining_accounts)?;
+        escrow_data
+    };
+
+    // Log the new escrow
+    escrow_data.event(EVENT_MAKE, escrow.key, amount, receive).emit(remaining_accounts)
 }
@@ -0,0 +1,25 @@
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+};
+use crate::{error::EscrowError, Escrow};
+
+/// Reallocate an escrow of an older layout version into the current layout, with Payer covering the extra rent
+pub fn process(accounts: &[AccountInfo<'_>]) -> ProgramResult {
+    let [payer, maker, escrow, _system_program] = accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Make sure the payer is a signer
+    if !payer.is_signer {
+        return Err(EscrowError::MissingSigner.into());
+    }
+
+    // Check & Get escrow account data
+    let (escrow_data, _) = Escrow::get_data_and_bump(maker.key, escrow)?;
+
+    // Migrate: Top up rent, Grow the escrow & Rewrite it in the current layout
+    Escrow::migrate(escrow_data, payer, escrow)
+}
@@ -1,23 +1,33 @@
 use solana_program::{
     account_info::AccountInfo,
+    clock::Clock,
     entrypoint::ProgramResult,
     program_error::ProgramError,
+    sysvar::Sysvar,
 };
-use crate::Escrow;
+use crate::{error::EscrowError, Escrow};
 
 /// Refund funds in vault to Maker's token account
 pub fn process(accounts: &[AccountInfo<'_>]) -> ProgramResult {
-    let [maker, mint_a, maker_ta_a, escrow, vault, token_program, _system_program] = accounts
+    let [maker, mint_a, maker_ta_a, escrow, vault, token_program, _system_program, remaining_accounts @ ..] = accounts
     else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
 
     // Make sure the maker is a signer
-    assert!(maker.is_signer);
+    if !maker.is_signer {
+        return Err(EscrowError::MissingSigner.into());
+    }
 
     // Check & Get escrow account data and bump
     let (escrow_data, bump) = Escrow::get_data_and_bump(maker.key, escrow)?;
 
+    // Make sure the escrow is not frozen by a dispute
+    escrow_data.check_dispute()?;
+
+    // Make sure an arbitrated escrow has expired before the maker acts alone
+    escrow_data.check_arbiter(Clock::get()?.unix_timestamp)?;
+
     // Refund: Transfer token A from vault to maker, Close the vault & escrow
-    Escrow::refund(escrow_data, bump, token_program.key, mint_a, maker, escrow, vault, maker_ta_a)
+    Escrow::refund(escrow_data, bump, token_program.key, mint_a, maker, escrow, vault, maker_ta_a, remaining_accounts)
 }
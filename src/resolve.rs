@@ -0,0 +1,34 @@
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+};
+use crate::{error::EscrowError, Escrow, Resolve};
+
+/// Arbiter splits the vault of a disputed escrow between Maker and Taker
+pub fn process(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult {
+    let Resolve {
+        maker_amount,
+        taker_amount,
+    } = Resolve::try_from(data)?;
+
+    let [arbiter, maker, mint_a, maker_ta_a, taker_ta_a, escrow, vault, token_program, _system_program, remaining_accounts @ ..] =
+        accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Check & Get escrow account data and bump
+    let (escrow_data, bump) = Escrow::get_data_and_bump(maker.key, escrow)?;
+
+    // Make sure the arbiter signed
+    if escrow_data.arbiter.eq(&Default::default()) || escrow_data.arbiter.ne(arbiter.key) {
+        return Err(EscrowError::InvalidArbiter.into());
+    }
+    if !arbiter.is_signer {
+        return Err(EscrowError::MissingSigner.into());
+    }
+
+    // Resolve: Transfer token A from vault to maker & taker, Close the vault & escrow
+    Escrow::resolve(escrow_data, bump, token_program.key, maker_amount, taker_amount, mint_a, maker, escrow, vault, maker_ta_a, taker_ta_a, remaining_accounts)
+}
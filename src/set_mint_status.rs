@@ -0,0 +1,25 @@
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+};
+use crate::{Config, MintEntry, SetMintStatus};
+
+/// Admin allows, denies or unlists a mint in the registry checked at Make
+pub fn process(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult {
+    let SetMintStatus { status } = SetMintStatus::try_from(data)?;
+
+    let [admin, config, mint, entry, _system_program] = accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Check & Get config account data
+    let config_data = Config::get(config)?;
+
+    // Make sure the admin signed
+    config_data.check_admin(admin)?;
+
+    // Set: Create, update or close the mint entry
+    MintEntry::set_status(status, mint.key, admin, entry)
+}
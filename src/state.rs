@@ -1,21 +1,188 @@
 use bytemuck::{Pod, Zeroable};
 use solana_program::{
     program::{invoke, invoke_signed},
-    system_instruction::create_account,
+    system_instruction::{create_account, transfer as system_transfer},
     account_info::AccountInfo, 
     entrypoint::ProgramResult, 
+    msg,
     program_error::ProgramError,
-    program_pack::Pack,
+    pubkey,
     pubkey::Pubkey,
     sysvar::Sysvar,
+    clock::Clock,
+    hash::hash,
     rent::Rent,
 };
-use spl_token::instruction::{transfer_checked, close_account};
-use crate::utils::{check_eq_program_derived_address, check_eq_program_derived_address_and_get_bump};
+use spl_token::native_mint;
+use spl_token_2022::{
+    extension::{
+        transfer_fee::instruction::harvest_withheld_tokens_to_mint, BaseStateWithExtensions, ExtensionType,
+        StateWithExtensions,
+    },
+    instruction::{close_account, initialize_account3},
+    state::{Account, Mint},
+};
+use crate::{
+    error::EscrowError,
+    events::{EscrowEvent, EVENT_CLAIM, EVENT_REFUND, EVENT_RESOLVE, EVENT_RESOLVE_MAKER, EVENT_TAKE, EVENT_VERSION},
+    utils::{
+        check_eq_program_address, check_eq_program_derived_address_and_get_bump, check_token_program, close_program_account,
+        get_pre_fee_amount, get_token_account_amount, get_token_account_owner, get_withheld_amount, pro_rata, transfer,
+    },
+};
+
+/// Maker receives `receive` net of mint B's transfer fee, with the taker paying the fee on top
+pub const FLAG_RECEIVE_NET_OF_FEE: u8 = 1 << 0;
+
+/// Maker deposits SOL, held as lamports in the escrow account instead of a vault
+pub const FLAG_NATIVE_A: u8 = 1 << 1;
+
+/// Taker pays SOL directly to the maker instead of mint B tokens
+pub const FLAG_NATIVE_B: u8 = 1 << 2;
+
+/// Receive scales with the vault on Deposit and Withdraw instead of staying fixed
+pub const FLAG_SCALE_RECEIVE: u8 = 1 << 3;
+
+const VALID_FLAGS: u8 = FLAG_RECEIVE_NET_OF_FEE | FLAG_NATIVE_A | FLAG_NATIVE_B | FLAG_SCALE_RECEIVE;
+
+/// Seed prefix of escrow PDAs, derived from `[ESCROW_SEED, maker, seed]`
+pub const ESCROW_SEED: &[u8] = b"escrow";
+
+/// Seed prefix of vault PDAs, derived from `[VAULT_SEED, escrow]`
+pub const VAULT_SEED: &[u8] = b"vault";
+
+/// Seed of the config PDA
+pub const CONFIG_SEED: &[u8] = b"config";
+
+/// Seed prefix of mint registry entry PDAs, derived from `[MINT_ENTRY_SEED, mint]`
+pub const MINT_ENTRY_SEED: &[u8] = b"mint";
+
+/// Leading byte identifying an escrow among the program's account kinds
+pub const ESCROW_DISCRIMINATOR: u8 = 1;
+
+/// Current escrow layout version, version 1 being the original layout without a header
+pub const ESCROW_VERSION: u8 = 2;
+
+/// Maximum number of approvers on an escrow
+pub const MAX_APPROVERS: usize = 5;
+
+/// Leading byte identifying the config among the program's account kinds
+pub const CONFIG_DISCRIMINATOR: u8 = 2;
+
+/// Current config layout version
+pub const CONFIG_VERSION: u8 = 1;
+
+/// Config PDA derived from `[b"config"]`, precomputed to skip the bump search on every Take
+pub const CONFIG_ADDRESS: Pubkey = pubkey!("EX2csvDdhkoEEQqBjvrmC31rb9iD5MZiUg2Dvf47Nx3x");
+
+/// Bump of `CONFIG_ADDRESS`
+pub const CONFIG_BUMP: u8 = 255;
+
+/// Protocol fee is skimmed from the mint A claimed by the taker instead of the mint B paid to the maker
+pub const CONFIG_FLAG_FEE_ON_A: u8 = 1 << 0;
+
+/// Make and Take are halted while Refund, Expire and Withdraw stay open
+pub const CONFIG_FLAG_PAUSED: u8 = 1 << 1;
+
+/// Make only lists mints with an allowed registry entry
+pub const CONFIG_FLAG_ALLOWLIST: u8 = 1 << 2;
+
+/// Make rejects mints with a denied registry entry
+pub const CONFIG_FLAG_DENYLIST: u8 = 1 << 3;
+
+const VALID_CONFIG_FLAGS: u8 = CONFIG_FLAG_FEE_ON_A | CONFIG_FLAG_PAUSED | CONFIG_FLAG_ALLOWLIST | CONFIG_FLAG_DENYLIST;
+
+/// Leading byte identifying a mint registry entry among the program's account kinds
+pub const MINT_ENTRY_DISCRIMINATOR: u8 = 3;
+
+/// Current mint registry entry layout version
+pub const MINT_ENTRY_VERSION: u8 = 1;
+
+/// Mint has no registry entry
+pub const MINT_STATUS_UNLISTED: u8 = 0;
+
+/// Mint may be listed under an allowlist
+pub const MINT_STATUS_ALLOWED: u8 = 1;
+
+/// Mint may not be listed under a denylist
+pub const MINT_STATUS_DENIED: u8 = 2;
+
+/// Basis points in 100%, for fees and premiums
+pub const BPS_DENOMINATOR: u16 = 10_000;
 
 #[repr(C)]
 #[derive(Clone, Copy, Pod, Zeroable)]
 pub struct Escrow {
+    pub discriminator: u8,
+    pub version: u8,
+    pub flags: u8,
+    pub bump: u8,
+    pub vault_bump: u8,
+    pub _padding: [u8; 3],
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub receive: u64,
+    pub expires_at: i64,
+    pub taker: Pubkey,
+    pub auction_start: i64,
+    pub auction_end: i64,
+    pub auction_end_receive: u64,
+    pub price_feed: Pubkey,
+    pub max_staleness: i64,
+    pub premium_bps: i16,
+    pub _padding_2: [u8; 6],
+    pub hashlock: [u8; 32],
+    pub approvers: [Pubkey; MAX_APPROVERS],
+    pub approvals: u8,
+    pub threshold: u8,
+    pub _padding_3: [u8; 6],
+    pub arbiter: Pubkey,
+    pub disputed: u8,
+    pub _padding_4: [u8; 7],
+}
+
+/// Global protocol settings, a singleton at `CONFIG_ADDRESS`
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct Config {
+    pub discriminator: u8,
+    pub version: u8,
+    pub flags: u8,
+    pub _padding: u8,
+    pub fee_bps: u16,
+    pub treasury: Pubkey,
+    pub admin: Pubkey,
+    pub pending_admin: Pubkey,
+}
+
+/// Registry entry for a mint, a PDA derived from `[b"mint", mint]`
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct MintEntry {
+    pub discriminator: u8,
+    pub version: u8,
+    pub status: u8,
+    pub bump: u8,
+    pub mint: Pubkey,
+}
+
+/// Price feed read by oracle-pegged escrows from the start of the feed account's data.
+/// `price` is the amount of mint B paid per 10^`exponent` of mint A, in base units, as of `published_at`
+#[repr(C)]
+#[derive(Clone, Copy, Default, Pod, Zeroable)]
+pub struct PriceFeed {
+    pub price: u64,
+    pub published_at: i64,
+    pub exponent: u8,
+    pub _padding: [u8; 7],
+}
+
+/// Original escrow layout, without a header, kept for reading and migrating open escrows
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct EscrowV1 {
     pub seed: u64,
     pub maker: Pubkey,
     pub mint_a: Pubkey,
@@ -23,33 +190,336 @@ pub struct Escrow {
     pub receive: u64,
 }
 
+impl Default for Escrow {
+    fn default() -> Self {
+        Self {
+            discriminator: ESCROW_DISCRIMINATOR,
+            version: ESCROW_VERSION,
+            ..Zeroable::zeroed()
+        }
+    }
+}
+
 impl Escrow {
     #[inline]
     pub fn get_data_and_bump(
         maker: &Pubkey,
         escrow: &AccountInfo,
     ) -> Result<(Escrow, u8), ProgramError>  {
-        // Get escrow data
-        let escrow_data: Escrow = *bytemuck::try_from_bytes::<Escrow>(*escrow.data.borrow())
-            .map_err(|_| ProgramError::InvalidAccountData)?;
+        // Check escrow is owned by the program
+        if escrow.owner.ne(&crate::ID) {
+            return Err(EscrowError::InvalidAccountOwner.into());
+        }
 
-        // Check PDA of escrow and get bump
-        let bump = check_eq_program_derived_address_and_get_bump(&[b"escrow", maker.as_ref(), escrow_data.seed.to_le_bytes().as_ref()], &crate::ID, escrow.key)?;
+        // Get escrow data of any layout version
+        let escrow_data = Escrow::read(escrow)?;
+
+        // Check PDA of escrow with the stored bump
+        check_eq_program_address(&[ESCROW_SEED, maker.as_ref(), escrow_data.seed.to_le_bytes().as_ref(), &[escrow_data.bump]], &crate::ID, escrow.key, EscrowError::InvalidEscrowAddress)?;
+
+        Ok((escrow_data, escrow_data.bump))
+    }
+
+    #[inline]
+    pub fn read(escrow: &AccountInfo) -> Result<Escrow, ProgramError> {
+        let data = escrow.try_borrow_data()?;
+
+        // Upgrade the original layout, deriving the bumps it did not store
+        if data.len() == core::mem::size_of::<EscrowV1>() {
+            let escrow_data = bytemuck::try_from_bytes::<EscrowV1>(&data)
+                .map_err(|_| ProgramError::InvalidAccountData)?;
+            let (_, bump) = Pubkey::find_program_address(&[ESCROW_SEED, escrow_data.maker.as_ref(), escrow_data.seed.to_le_bytes().as_ref()], &crate::ID);
+            let (_, vault_bump) = Pubkey::find_program_address(&[VAULT_SEED, escrow.key.as_ref()], &crate::ID);
+
+            return Ok(Escrow {
+                version: 1,
+                bump,
+                vault_bump,
+                seed: escrow_data.seed,
+                maker: escrow_data.maker,
+                mint_a: escrow_data.mint_a,
+                mint_b: escrow_data.mint_b,
+                receive: escrow_data.receive,
+                ..Default::default()
+            });
+        }
+
+        // Check account kind and layout version
+        let [discriminator, version, ..] = data[..] else {
+            return Err(ProgramError::InvalidAccountData);
+        };
+        if discriminator != ESCROW_DISCRIMINATOR {
+            return Err(EscrowError::InvalidDiscriminator.into());
+        }
+        if version != ESCROW_VERSION {
+            return Err(EscrowError::UnsupportedVersion.into());
+        }
+
+        bytemuck::try_pod_read_unaligned::<Escrow>(&data)
+            .map_err(|_| ProgramError::InvalidAccountData)
+    }
+
+    #[inline]
+    pub fn migrate<'a>(
+        mut escrow_data: Escrow,
+        payer: &AccountInfo<'a>,
+        escrow: &AccountInfo<'a>,
+    ) -> ProgramResult {
+        // Check escrow is not already on the current layout
+        if escrow_data.version == ESCROW_VERSION {
+            return Err(EscrowError::AlreadyMigrated.into());
+        }
+
+        let space = core::mem::size_of::<Escrow>();
+        let rent = Rent::get()?;
+
+        // Top up rent for the larger layout, leaving any SOL held above rent untouched
+        let rent_difference = rent.minimum_balance(space).saturating_sub(rent.minimum_balance(escrow.data_len()));
+        if rent_difference > 0 {
+            invoke(
+                &system_transfer(payer.key, escrow.key, rent_difference),
+                &[
+                    payer.clone(),
+                    escrow.clone(),
+                ],
+            )?;
+        }
+
+        // Grow the account and rewrite it in the current layout
+        escrow.realloc(space, true)?;
+        escrow_data.discriminator = ESCROW_DISCRIMINATOR;
+        escrow_data.version = ESCROW_VERSION;
+        escrow_data.save(escrow)
+    }
+
+    #[inline]
+    pub fn is_expired(&self, now: i64) -> bool {
+        // An expiry of zero means the escrow never expires
+        self.expires_at != 0 && now >= self.expires_at
+    }
+
+    #[inline]
+    pub fn is_native_a(&self) -> bool {
+        self.flags & FLAG_NATIVE_A != 0
+    }
+
+    #[inline]
+    pub fn is_native_b(&self) -> bool {
+        self.flags & FLAG_NATIVE_B != 0
+    }
+
+    #[inline]
+    pub fn is_scaled(&self) -> bool {
+        self.flags & FLAG_SCALE_RECEIVE != 0
+    }
+
+    #[inline]
+    pub fn is_hashlocked(&self) -> bool {
+        // A zero hashlock means the escrow is not an HTLC
+        self.hashlock.ne(&[0; 32])
+    }
+
+    #[inline]
+    pub fn is_approved(&self) -> bool {
+        // A threshold of zero means no approvals are required
+        self.approvals.count_ones() >= self.threshold as u32
+    }
+
+    #[inline]
+    pub fn approve(mut escrow_data: Escrow, approver: &Pubkey, escrow: &AccountInfo) -> ProgramResult {
+        // Check the signer is one of the approvers
+        let index = escrow_data
+            .approvers
+            .iter()
+            .position(|key| key.ne(&Pubkey::default()) && key.eq(approver))
+            .ok_or(EscrowError::InvalidApprover)?;
+
+        // Record the approval
+        escrow_data.approvals |= 1 << index;
+        escrow_data.save(escrow)
+    }
+
+    #[inline]
+    pub fn is_auction(&self) -> bool {
+        // An auction end of zero means a fixed price
+        self.auction_end != 0
+    }
+
+    #[inline]
+    pub fn get_receive(&self, now: i64) -> u64 {
+        if !self.is_auction() || now <= self.auction_start {
+            return self.receive;
+        }
+        if now >= self.auction_end {
+            return self.auction_end_receive;
+        }
+
+        // Decay linearly from receive to the end price, rounded up in favour of the maker
+        let decay = (self.receive - self.auction_end_receive) as u128;
+        let remaining = (self.auction_end - now) as u128;
+        let duration = (self.auction_end - self.auction_start) as u128;
+        self.auction_end_receive + (decay * remaining).div_ceil(duration) as u64
+    }
+
+    #[inline]
+    pub fn is_oracle(&self) -> bool {
+        // A default price feed means the escrow is not pegged
+        self.price_feed.ne(&Pubkey::default())
+    }
+
+    #[inline]
+    pub fn get_oracle_receive(&self, vault_amount: u64, now: i64, remaining_accounts: &[AccountInfo]) -> Result<u64, ProgramError> {
+        // Find the price feed among the remaining accounts
+        let price_feed = remaining_accounts
+            .iter()
+            .find(|account| account.key.eq(&self.price_feed))
+            .ok_or(EscrowError::InvalidPriceFeed)?;
+        let feed = bytemuck::try_pod_read_unaligned::<PriceFeed>(
+            price_feed.try_borrow_data()?.get(..core::mem::size_of::<PriceFeed>()).ok_or(EscrowError::InvalidPriceFeed)?,
+        )
+        .map_err(|_| EscrowError::InvalidPriceFeed)?;
+
+        // Check the price is set, fresh, and not published in the future
+        if feed.price == 0 || feed.published_at > now {
+            return Err(EscrowError::InvalidPriceFeed.into());
+        }
+        if now.saturating_sub(feed.published_at) > self.max_staleness {
+            return Err(EscrowError::StalePrice.into());
+        }
+
+        // Price the vault at the feed, then apply the premium or discount, rounded up in favour of the maker
+        let scale = 10u128.checked_pow(feed.exponent as u32).ok_or(EscrowError::InvalidPriceFeed)?;
+        let receive = (vault_amount as u128 * feed.price as u128).div_ceil(scale);
+        let receive = receive
+            .checked_mul((BPS_DENOMINATOR as i32 + self.premium_bps as i32) as u128)
+            .ok_or(ProgramError::ArithmeticOverflow)?
+            .div_ceil(BPS_DENOMINATOR as u128);
+        u64::try_from(receive).map_err(|_| ProgramError::ArithmeticOverflow)
+    }
+
+    #[inline]
+    pub fn scale_price(&mut self, amount: u64, total: u64) -> ProgramResult {
+        // Scale the start and end prices alike
+        self.receive = pro_rata(self.receive, amount, total)?;
+        self.auction_end_receive = pro_rata(self.auction_end_receive, amount, total)?;
+        Ok(())
+    }
+
+    #[inline]
+    pub fn event(&self, kind: u8, escrow: &Pubkey, amount_a: u64, amount_b: u64) -> EscrowEvent {
+        EscrowEvent {
+            version: EVENT_VERSION,
+            kind,
+            seed: self.seed,
+            escrow: *escrow,
+            maker: self.maker,
+            taker: self.taker,
+            mint_a: self.mint_a,
+            mint_b: self.mint_b,
+            amount_a,
+            amount_b,
+            ..Default::default()
+        }
+    }
+
+    #[inline]
+    pub fn check_vault(&self, escrow: &Pubkey, vault: &Pubkey) -> ProgramResult {
+        // Check PDA of vault with the stored bump
+        check_eq_program_address(&[VAULT_SEED, escrow.as_ref(), &[self.vault_bump]], &crate::ID, vault, EscrowError::InvalidVaultAddress)
+    }
+
+    #[inline]
+    pub fn get_vault_amount(&self, escrow: &AccountInfo, vault: &AccountInfo) -> Result<u64, ProgramError> {
+        if self.is_native_a() {
+            Ok(escrow.lamports().saturating_sub(Rent::get()?.minimum_balance(escrow.data_len())))
+        } else {
+            self.check_vault(escrow.key, vault.key)?;
+            get_token_account_amount(vault)
+        }
+    }
+
+    #[inline]
+    pub fn save(&self, escrow: &AccountInfo) -> ProgramResult {
+        // Older layouts must be migrated before they can be written
+        if self.version != ESCROW_VERSION || escrow.data_len() != core::mem::size_of::<Escrow>() {
+            return Err(EscrowError::MigrationRequired.into());
+        }
+
+        *bytemuck::try_from_bytes_mut::<Escrow>(*escrow.try_borrow_mut_data()?)
+            .map_err(|_| ProgramError::InvalidAccountData)? = *self;
+        Ok(())
+    }
+
+    #[inline]
+    pub fn check_terms(&self) -> ProgramResult {
+        // Check flags are known
+        if self.flags & !VALID_FLAGS != 0 {
+            return Err(EscrowError::InvalidFlags.into());
+        }
+
+        // Check native SOL sides use the native mint, and at most one side is native
+        if (self.is_native_a() && self.mint_a.ne(&native_mint::ID))
+            || (self.is_native_b() && self.mint_b.ne(&native_mint::ID))
+            || (self.is_native_a() && self.is_native_b())
+        {
+            return Err(EscrowError::InvalidFlags.into());
+        }
+
+        // Check expiry is in the future
+        if self.is_expired(Clock::get()?.unix_timestamp) {
+            return Err(EscrowError::InvalidExpiry.into());
+        }
+
+        // Check an auction runs forwards and decays towards its end price
+        if self.is_auction() && (self.auction_start >= self.auction_end || self.auction_end_receive > self.receive) {
+            return Err(EscrowError::InvalidAuction.into());
+        }
+
+        // Check the threshold can be met by the approvers
+        if self.threshold as usize > self.approvers.iter().filter(|&&key| key.ne(&Pubkey::default())).count() {
+            return Err(EscrowError::InvalidThreshold.into());
+        }
 
-        Ok((escrow_data, bump))
+        // Check approvers are distinct, packed at the front with no default keys among them
+        let used = self.approvers.iter().take_while(|&&key| key.ne(&Pubkey::default())).count();
+        if self.approvers[used..].iter().any(|&key| key.ne(&Pubkey::default()))
+            || (1..used).any(|i| self.approvers[..i].contains(&self.approvers[i]))
+        {
+            return Err(EscrowError::InvalidApprover.into());
+        }
+
+        // Check an arbiter has a designated taker to split the vault with
+        if self.arbiter.ne(&Pubkey::default()) && self.taker.eq(&Pubkey::default()) {
+            return Err(EscrowError::InvalidArbiter.into());
+        }
+
+        // Check a hashlock has a designated taker to release to and a timeout to refund after
+        if self.is_hashlocked() && (self.taker.eq(&Pubkey::default()) || self.expires_at == 0) {
+            return Err(EscrowError::InvalidHashlock.into());
+        }
+
+        // Check an oracle peg has a staleness bound, a discount under 100% and no auction
+        if self.is_oracle() && (self.max_staleness <= 0 || self.premium_bps <= -(BPS_DENOMINATOR as i16) || self.is_auction()) {
+            return Err(EscrowError::InvalidPriceFeed.into());
+        }
+
+        Ok(())
     }
 
     #[inline]
     pub fn init<'a>(
-        seed: u64,
-        receive: u64,
-        mint_a: Pubkey,
-        mint_b: Pubkey,
+        mut escrow_data: Escrow,
         maker: &AccountInfo<'a>,
         escrow: &AccountInfo<'a>,
-    ) -> ProgramResult {
+    ) -> Result<Escrow, ProgramError> {
+        let seed = escrow_data.seed;
+
+        // Check escrow terms
+        escrow_data.check_terms()?;
+
         // Check PDA of escrow and get bump
-        let bump = check_eq_program_derived_address_and_get_bump(&[b"escrow", maker.key.as_ref(), seed.to_le_bytes().as_ref()], &crate::ID, escrow.key)?;
+        let bump = check_eq_program_derived_address_and_get_bump(&[ESCROW_SEED, maker.key.as_ref(), seed.to_le_bytes().as_ref()], &crate::ID, escrow.key, EscrowError::InvalidEscrowAddress)?;
+        escrow_data.bump = bump;
 
         let space = core::mem::size_of::<Escrow>();
         let rent = Rent::get()?.minimum_balance(space);
@@ -69,7 +539,7 @@ impl Escrow {
             ],
             &[
                 &[
-                    b"escrow",
+                    ESCROW_SEED,
                     maker.key.as_ref(),
                     seed.to_le_bytes().as_ref(),
                     &[bump],
@@ -79,22 +549,108 @@ impl Escrow {
 
         escrow.assign(&crate::ID);
         // Create the escrow
-        let mut escrow_data: Escrow =
-            *bytemuck::try_from_bytes_mut::<Escrow>(*escrow.data.borrow_mut())
-                .map_err(|_| ProgramError::InvalidAccountData)?;
-        escrow_data.clone_from(&Escrow {
-            seed,
-            maker: *maker.key,
-            mint_a,
-            mint_b,
-            receive,
-        });
+        escrow_data.save(escrow)?;
 
-        Ok(())
+        Ok(escrow_data)
+    }
+
+    #[inline]
+    pub fn update(
+        mut escrow_data: Escrow,
+        receive: u64,
+        expires_at: Option<i64>,
+        mint_b: &Pubkey,
+        escrow: &AccountInfo,
+    ) -> ProgramResult {
+        // A hashlock's timeout must not move once the taker may have revealed the preimage
+        if escrow_data.is_hashlocked() {
+            return Err(EscrowError::HashlockedEscrow.into());
+        }
+
+        // An expired escrow is left to Refund or Expire rather than brought back to life
+        if escrow_data.is_expired(Clock::get()?.unix_timestamp) {
+            return Err(EscrowError::EscrowExpired.into());
+        }
+
+        // Reprice the escrow in place, clearing approvals given for the old terms
+        escrow_data.receive = receive;
+        escrow_data.expires_at = expires_at.unwrap_or(escrow_data.expires_at);
+        escrow_data.mint_b = *mint_b;
+        escrow_data.approvals = 0;
+
+        // Check escrow terms
+        escrow_data.check_terms()?;
+
+        escrow_data.save(escrow)
+    }
+
+    #[inline]
+    pub fn init_vault<'a>(
+        escrow_address: &Pubkey,
+        token_program: &Pubkey,
+        mint_a: &AccountInfo<'a>,
+        vault: &AccountInfo<'a>,
+        maker: &AccountInfo<'a>,
+    ) -> Result<u8, ProgramError> {
+        // Check PDA of vault and get bump
+        let bump = check_eq_program_derived_address_and_get_bump(&[VAULT_SEED, escrow_address.as_ref()], &crate::ID, vault.key, EscrowError::InvalidVaultAddress)?;
+
+        // Check token program
+        check_token_program(token_program)?;
+
+        // Size the vault for the account extensions required by the mint
+        let space = {
+            let mint_data = mint_a.try_borrow_data()?;
+            let mint = StateWithExtensions::<Mint>::unpack(&mint_data)?;
+            ExtensionType::try_calculate_account_len::<Account>(
+                &ExtensionType::get_required_init_account_extensions(&mint.get_extension_types()?),
+            )?
+        };
+        let rent = Rent::get()?.minimum_balance(space);
+
+        // Create the Vault Account
+        invoke_signed(
+            &create_account(
+                maker.key,
+                vault.key,
+                rent,
+                space as u64,
+                token_program,
+            ),
+            &[
+                maker.clone(), 
+                vault.clone()
+            ],
+            &[
+                &[
+                    VAULT_SEED,
+                    escrow_address.as_ref(),
+                    &[bump],
+                ]
+            ],
+        )?;
+
+        // Initialize the vault with the escrow as its owner
+        invoke(
+            &initialize_account3(
+                token_program,
+                vault.key,
+                mint_a.key,
+                escrow_address,
+            )?,
+            &[
+                vault.clone(),
+                mint_a.clone(),
+            ],
+        )?;
+
+        Ok(bump)
     }
 
     #[inline]
+    #[allow(clippy::too_many_arguments)]
     pub fn deposit<'a>(
+        escrow_data: &Escrow,
         escrow_address: &Pubkey,
         token_program: &Pubkey,
         amount: u64,
@@ -102,158 +658,382 @@ impl Escrow {
         mint_a: &AccountInfo<'a>,
         vault: &AccountInfo<'a>,
         maker: &AccountInfo<'a>,
+        remaining_accounts: &[AccountInfo<'a>],
     ) -> ProgramResult {
         // Check PDA of vault
-        check_eq_program_derived_address(&[b"vault", escrow_address.as_ref()], &crate::ID, vault.key)?;
+        escrow_data.check_vault(escrow_address, vault.key)?;
 
-        // By checking this, we know our token accounts are correct by virtue of Token Program checking them
-        assert!([&spl_token::ID, &spl_token_2022::ID].contains(&token_program));
+        // Check token program
+        check_token_program(token_program)?;
 
         // Check if the vault is owned by the escrow
-        assert_eq!(
-            *escrow_address,
-            *<spl_token::state::Account as spl_token::state::GenericTokenAccount>::unpack_account_owner(*vault.try_borrow_data()?)
-            .ok_or(ProgramError::InvalidAccountData)?
-        );
-    
-        // Get token decimals
-        let decimals = spl_token::state::Mint::unpack(&mint_a.try_borrow_data()?)?.decimals;
+        if escrow_address.ne(&get_token_account_owner(vault)?) {
+            return Err(EscrowError::InvalidVaultOwner.into());
+        }
     
         // Transfer the funds from the maker's token account to the vault
+        transfer(token_program, maker_ta_a, mint_a, vault, maker, amount, &[], remaining_accounts)?;
+
+        Ok(())
+    }
+
+    #[inline]
+    pub fn deposit_native<'a>(
+        amount: u64,
+        escrow: &AccountInfo<'a>,
+        maker: &AccountInfo<'a>,
+    ) -> ProgramResult {
+        // Transfer SOL from the maker to the escrow
         invoke(
-            &transfer_checked(
-                token_program,
-                maker_ta_a.key,
-                mint_a.key,
-                vault.key,
-                maker.key,
-                &[],
-                amount,
-                decimals,
-            )?,
+            &system_transfer(maker.key, escrow.key, amount),
             &[
-                maker_ta_a.clone(),
-                mint_a.clone(),
-                vault.clone(),
                 maker.clone(),
+                escrow.clone(),
             ],
-        )?;
+        )
+    }
 
-        Ok(())
+    #[inline]
+    #[allow(clippy::too_many_arguments)]
+    pub fn top_up<'a>(
+        mut escrow_data: Escrow,
+        token_program: &Pubkey,
+        amount: u64,
+        mint_a: &AccountInfo<'a>,
+        maker: &AccountInfo<'a>,
+        maker_ta_a: &AccountInfo<'a>,
+        escrow: &AccountInfo<'a>,
+        vault: &AccountInfo<'a>,
+        remaining_accounts: &[AccountInfo<'a>],
+    ) -> ProgramResult {
+
+        // Check mints match
+        if mint_a.key.ne(&escrow_data.mint_a) {
+            return Err(EscrowError::MintMismatch.into());
+        }
+
+        // Check amount
+        if amount == 0 {
+            return Err(EscrowError::InvalidAmount.into());
+        }
+
+        // Get token amount before the deposit
+        let vault_amount = escrow_data.get_vault_amount(escrow, vault)?;
+
+        // Deposit funds into the escrow or vault
+        if escrow_data.is_native_a() {
+            Escrow::deposit_native(amount, escrow, maker)?;
+        } else {
+            Escrow::deposit(&escrow_data, escrow.key, token_program, amount, maker_ta_a, mint_a, vault, maker, remaining_accounts)?;
+        }
+
+        // Clear approvals given for the old vault amount
+        escrow_data.approvals = 0;
+
+        // Keep receive fixed unless the escrow is priced proportionally
+        if escrow_data.is_scaled() {
+            // Scale receive by what the vault actually received, net of any transfer fee
+            let new_vault_amount = escrow_data.get_vault_amount(escrow, vault)?;
+            escrow_data.scale_price(new_vault_amount, vault_amount)?;
+        }
+        escrow_data.save(escrow)
     }
 
     #[inline]
-    pub fn take<'a>(
-        escrow_data: Escrow,
+    #[allow(clippy::too_many_arguments)]
+    pub fn withdraw<'a>(
+        mut escrow_data: Escrow,
         bump: u8,
         token_program: &Pubkey,
+        amount: u64,
         mint_a: &AccountInfo<'a>,
-        mint_b: &AccountInfo<'a>,
         maker: &AccountInfo<'a>,
-        taker: &AccountInfo<'a>,
+        maker_ta_a: &AccountInfo<'a>,
         escrow: &AccountInfo<'a>,
         vault: &AccountInfo<'a>,
-        maker_ta_b: &AccountInfo<'a>,
-        taker_ta_a: &AccountInfo<'a>,
-        taker_ta_b: &AccountInfo<'a>,
+        remaining_accounts: &[AccountInfo<'a>],
     ) -> ProgramResult {
 
-        // Check PDA of vault
-        check_eq_program_derived_address(&[b"vault", escrow.key.as_ref()], &crate::ID, vault.key)?;
+        // A hashlocked vault must stay whole until it is claimed or times out
+        if escrow_data.is_hashlocked() {
+            return Err(EscrowError::HashlockedEscrow.into());
+        }
 
         // Check mints match
-        assert_eq!(mint_a.key, &escrow_data.mint_a);
-        assert_eq!(mint_b.key, &escrow_data.mint_b);
+        if mint_a.key.ne(&escrow_data.mint_a) {
+            return Err(EscrowError::MintMismatch.into());
+        }
 
-        // Get token decimals
-        let decimals_a = spl_token::state::Mint::unpack(&mint_a.try_borrow_data()?)?.decimals;
-        let decimals_b = spl_token::state::Mint::unpack(&mint_b.try_borrow_data()?)?.decimals;
+        // Get token amount, held as lamports above rent in the escrow itself for native SOL
+        let vault_amount = escrow_data.get_vault_amount(escrow, vault)?;
 
-        // Get token amount
-        let amount = spl_token::state::Account::unpack(&vault.try_borrow_data()?)?.amount;
+        // Check amount leaves funds in the vault, Refund closes the escrow instead
+        if amount == 0 || amount >= vault_amount {
+            return Err(EscrowError::InvalidAmount.into());
+        }
 
-        // By checking this, we know our token accounts are correct by virtue of Token Program checking them
-        assert!([&spl_token::ID, &spl_token_2022::ID].contains(&token_program));
+        if escrow_data.is_native_a() {
+            // Withdraw SOL to maker
+            **escrow.lamports.borrow_mut() -= amount;
+            **maker.lamports.borrow_mut() += amount;
+        } else {
+            // Check token program
+            check_token_program(token_program)?;
 
-         // Claim token A to taker
-        invoke_signed(
-            &transfer_checked(
+            // Withdraw token A to maker
+            transfer(
                 token_program,
-                vault.key,
-                mint_a.key,
-                taker_ta_a.key,
-                escrow.key,
-                &[],
+                vault,
+                mint_a,
+                maker_ta_a,
+                escrow,
                 amount,
-                decimals_a,
-            )?,
-            &[
-                vault.clone(),
-                mint_a.clone(),
-                taker_ta_a.clone(),
-                escrow.clone(),
-            ],
-            &[&[
-                b"escrow",
-                maker.key.as_ref(),
-                escrow_data.seed.to_le_bytes().as_ref(),
-                &[bump],
-            ]],
-        )?;
+                &[&[
+                    ESCROW_SEED,
+                    maker.key.as_ref(),
+                    escrow_data.seed.to_le_bytes().as_ref(),
+                    &[bump],
+                ]],
+                remaining_accounts,
+            )?;
+        }
 
-        // Transfer token B to maker
-        invoke(
-            &transfer_checked(
-                token_program,
-                taker_ta_b.key,
-                mint_b.key,
-                maker_ta_b.key,
-                taker.key,
-                &[],
-                escrow_data.receive,
-                decimals_b,
-            )?,
-            &[
-                taker_ta_b.clone(),
-                mint_b.clone(),
-                maker_ta_b.clone(),
-                taker.clone(),
-            ],
-        )?;
+        // Clear approvals given for the old vault amount
+        escrow_data.approvals = 0;
 
-        // Close the vault
-        invoke_signed(
-            &close_account(
-                token_program, 
-                vault.key, 
-                maker.key, 
-                escrow.key, 
-                &[]
-            )?,
-            &[
-                vault.clone(), 
-                maker.clone(), 
-                escrow.clone()
-            ],
-            &[&[
-                b"escrow",
-                maker.key.as_ref(),
-                escrow_data.seed.to_le_bytes().as_ref(),
-                &[bump],
-            ]],
-        )?;
+        // Keep receive fixed unless the escrow is priced proportionally
+        if escrow_data.is_scaled() {
+            // Scale receive down to the funds left in the vault
+            escrow_data.scale_price(vault_amount - amount, vault_amount)?;
+        }
+        escrow_data.save(escrow)
+    }
 
-        // Close the escrow
-        let balance = escrow.lamports();
-        escrow.realloc(0, false)?;
-        **escrow.lamports.borrow_mut() = 0;
-        **maker.lamports.borrow_mut() += balance;
-        escrow.assign(&Pubkey::default());
+    #[inline]
+    #[allow(clippy::too_many_arguments)]
+    pub fn take<'a>(
+        mut escrow_data: Escrow,
+        bump: u8,
+        token_program: &Pubkey,
+        mint_a: &AccountInfo<'a>,
+        mint_b: &AccountInfo<'a>,
+        maker: &AccountInfo<'a>,
+        taker: &AccountInfo<'a>,
+        escrow: &AccountInfo<'a>,
+        vault: &AccountInfo<'a>,
+        maker_ta_b: &AccountInfo<'a>,
+        taker_ta_a: &AccountInfo<'a>,
+        taker_ta_b: &AccountInfo<'a>,
+        config_data: Option<Config>,
+        treasury: &AccountInfo<'a>,
+        fill: Option<u64>,
+        remaining_accounts: &[AccountInfo<'a>],
+    ) -> ProgramResult {
 
-        Ok(())
+        // A hashlocked escrow is settled by Claim instead
+        if escrow_data.is_hashlocked() {
+            return Err(EscrowError::HashlockedEscrow.into());
+        }
+
+        // Check taker is the designated counterparty, if any
+        if escrow_data.taker.ne(&Pubkey::default()) {
+            if escrow_data.taker.ne(taker.key) {
+                return Err(EscrowError::InvalidTaker.into());
+            }
+            if !taker.is_signer {
+                return Err(EscrowError::MissingSigner.into());
+            }
+        }
+
+        // Check enough approvers signed off on settlement
+        if !escrow_data.is_approved() {
+            return Err(EscrowError::ApprovalsPending.into());
+        }
+
+        // Check mints match
+        if mint_a.key.ne(&escrow_data.mint_a) || mint_b.key.ne(&escrow_data.mint_b) {
+            return Err(EscrowError::MintMismatch.into());
+        }
+
+        // Get token amount, held as lamports above rent in the escrow itself for native SOL
+        let vault_amount = escrow_data.get_vault_amount(escrow, vault)?;
+
+        // Take the whole vault unless a partial fill was requested
+        let amount = fill.unwrap_or(vault_amount);
+        if amount == 0 || amount > vault_amount {
+            return Err(EscrowError::InvalidFillAmount.into());
+        }
+
+        // Get the current price, pegged to the price feed or decayed over the auction if any
+        let now = Clock::get()?.unix_timestamp;
+        let total_receive = if escrow_data.is_oracle() {
+            escrow_data.get_oracle_receive(vault_amount, now, remaining_accounts)?
+        } else {
+            escrow_data.get_receive(now)
+        };
+
+        // Pay the pro-rata share of receive, rounded up in favour of the maker
+        let receive = if amount == vault_amount {
+            total_receive
+        } else {
+            pro_rata(total_receive, amount, vault_amount)?
+        };
+
+        // Skim the protocol fee from mint B paid to maker, or from mint A claimed by taker
+        let (fee_a, fee_b) = match config_data {
+            Some(config_data) if config_data.is_fee_on_a() => (config_data.fee(amount), 0),
+            Some(config_data) => (0, config_data.fee(receive)),
+            None => (0, 0),
+        };
+
+        // Check token program
+        check_token_program(token_program)?;
+
+        if escrow_data.is_native_b() {
+            // Transfer SOL to maker
+            invoke(
+                &system_transfer(taker.key, maker.key, receive - fee_b),
+                &[
+                    taker.clone(),
+                    maker.clone(),
+                ],
+            )?;
+        } else {
+            // Make sure the payment goes to the maker
+            if maker.key.ne(&get_token_account_owner(maker_ta_b)?) {
+                return Err(EscrowError::InvalidMakerTokenAccount.into());
+            }
+
+            // Gross up the payment so the maker receives it net of mint B's transfer fee
+            let payment = if escrow_data.flags & FLAG_RECEIVE_NET_OF_FEE != 0 {
+                get_pre_fee_amount(mint_b, receive - fee_b)?
+            } else {
+                receive - fee_b
+            };
+
+            // Transfer token B to maker
+            transfer(token_program, taker_ta_b, mint_b, maker_ta_b, taker, payment, &[], remaining_accounts)?;
+        }
+
+        if let Some(config_data) = config_data.filter(|_| fee_b > 0) {
+            // Check treasury
+            config_data.check_treasury(escrow_data.is_native_b(), treasury)?;
+
+            if escrow_data.is_native_b() {
+                // Transfer SOL fee to treasury
+                invoke(
+                    &system_transfer(taker.key, treasury.key, fee_b),
+                    &[
+                        taker.clone(),
+                        treasury.clone(),
+                    ],
+                )?;
+            } else {
+                // Transfer token B fee to treasury
+                transfer(token_program, taker_ta_b, mint_b, treasury, taker, fee_b, &[], remaining_accounts)?;
+            }
+            msg!("Protocol fee: {} of mint {} to {}", fee_b, escrow_data.mint_b, treasury.key);
+        }
+
+        let escrow_seeds: &[&[u8]] = &[
+            ESCROW_SEED,
+            maker.key.as_ref(),
+            &escrow_data.seed.to_le_bytes(),
+            &[bump],
+        ];
+
+        if escrow_data.is_native_a() {
+            // Claim SOL to taker
+            **escrow.lamports.borrow_mut() -= amount - fee_a;
+            **taker_ta_a.lamports.borrow_mut() += amount - fee_a;
+        } else {
+            // Claim token A to taker
+            transfer(token_program, vault, mint_a, taker_ta_a, escrow, amount - fee_a, &[escrow_seeds], remaining_accounts)?;
+        }
+
+        if let Some(config_data) = config_data.filter(|_| fee_a > 0) {
+            // Check treasury
+            config_data.check_treasury(escrow_data.is_native_a(), treasury)?;
+
+            if escrow_data.is_native_a() {
+                // Transfer SOL fee to treasury
+                **escrow.lamports.borrow_mut() -= fee_a;
+                **treasury.lamports.borrow_mut() += fee_a;
+            } else {
+                // Transfer token A fee to treasury
+                transfer(token_program, vault, mint_a, treasury, escrow, fee_a, &[escrow_seeds], remaining_accounts)?;
+            }
+            msg!("Protocol fee: {} of mint {} to {}", fee_a, escrow_data.mint_a, treasury.key);
+        }
+
+        // Log the fill
+        EscrowEvent {
+            taker: *taker.key,
+            fee_a,
+            fee_b,
+            ..escrow_data.event(EVENT_TAKE, escrow.key, amount, receive)
+        }
+        .emit(remaining_accounts)?;
+
+        // Keep the escrow open with the remaining receive until fully filled
+        if amount < vault_amount {
+            escrow_data.receive -= pro_rata(escrow_data.receive, amount, vault_amount)?;
+            escrow_data.auction_end_receive -= pro_rata(escrow_data.auction_end_receive, amount, vault_amount)?;
+            return escrow_data.save(escrow);
+        }
+
+        // Close the vault & escrow
+        escrow_data.close_all(bump, token_program, mint_a, maker, escrow, vault)
     }
 
     #[inline]
+    #[allow(clippy::too_many_arguments)]
+    pub fn claim<'a>(
+        escrow_data: Escrow,
+        bump: u8,
+        token_program: &Pubkey,
+        preimage: &[u8; 32],
+        mint_a: &AccountInfo<'a>,
+        maker: &AccountInfo<'a>,
+        escrow: &AccountInfo<'a>,
+        vault: &AccountInfo<'a>,
+        taker_ta_a: &AccountInfo<'a>,
+        remaining_accounts: &[AccountInfo<'a>],
+    ) -> ProgramResult {
+
+        // Check the escrow is hashlocked and the preimage matches
+        if !escrow_data.is_hashlocked() || hash(preimage).to_bytes().ne(&escrow_data.hashlock) {
+            return Err(EscrowError::InvalidPreimage.into());
+        }
+
+        // Check enough approvers signed off on settlement
+        if !escrow_data.is_approved() {
+            return Err(EscrowError::ApprovalsPending.into());
+        }
+
+        // Check mints match
+        if mint_a.key.ne(&escrow_data.mint_a) {
+            return Err(EscrowError::MintMismatch.into());
+        }
+
+        // Get token amount, held as lamports above rent in the escrow itself for native SOL
+        let amount = escrow_data.get_vault_amount(escrow, vault)?;
+
+        // Log the claim
+        escrow_data.event(EVENT_CLAIM, escrow.key, amount, 0).emit(remaining_accounts)?;
+
+        // Make sure the funds go to the designated taker
+        escrow_data.check_recipient(taker_ta_a, &escrow_data.taker, EscrowError::InvalidTaker)?;
+
+        // Release the vault funds to taker
+        escrow_data.release(bump, token_program, mint_a, maker, escrow, vault, taker_ta_a, amount, remaining_accounts)?;
+
+        // Close the vault & escrow
+        escrow_data.close_all(bump, token_program, mint_a, maker, escrow, vault)
+    }
+
+    #[inline]
+    #[allow(clippy::too_many_arguments)]
     pub fn refund<'a>(
         escrow_data: Escrow,
         bump: u8,
@@ -263,48 +1043,219 @@ impl Escrow {
         escrow: &AccountInfo<'a>,
         vault: &AccountInfo<'a>,
         maker_ta_a: &AccountInfo<'a>,
+        remaining_accounts: &[AccountInfo<'a>],
     ) -> ProgramResult {
 
-        // Check PDA of vault
-        check_eq_program_derived_address(&[b"vault", escrow.key.as_ref()], &crate::ID, vault.key)?;
+        // A hashlocked escrow can only be refunded after its timeout
+        if escrow_data.is_hashlocked() && !escrow_data.is_expired(Clock::get()?.unix_timestamp) {
+            return Err(EscrowError::EscrowNotExpired.into());
+        }
 
         // Check mints match
-        assert_eq!(mint_a.key, &escrow_data.mint_a);
+        if mint_a.key.ne(&escrow_data.mint_a) {
+            return Err(EscrowError::MintMismatch.into());
+        }
 
-        // Get token decimals
-        let decimals = spl_token::state::Mint::unpack(&mint_a.try_borrow_data()?)?.decimals;
+        // Get token amount, held as lamports above rent in the escrow itself for native SOL
+        let amount = escrow_data.get_vault_amount(escrow, vault)?;
 
-        // Get token amount
-        let amount = spl_token::state::Account::unpack(&vault.try_borrow_data()?)?.amount;
+        // Log the refund
+        escrow_data.event(EVENT_REFUND, escrow.key, amount, 0).emit(remaining_accounts)?;
 
-        // By checking this, we know our token accounts are correct by virtue of Token Program checking them
-        assert!([&spl_token::ID, &spl_token_2022::ID].contains(&token_program));
+        // Native SOL is refunded to the maker by closing the escrow
+        if escrow_data.is_native_a() {
+            return close_program_account(maker, escrow);
+        }
 
         // Refund the vault funds
-        invoke_signed(
-            &transfer_checked(
-                token_program,
-                vault.key,
-                mint_a.key,
-                maker_ta_a.key,
-                escrow.key,
-                &[],
-                amount,
-                decimals,
-            )?,
-            &[
-                vault.clone(),
-                mint_a.clone(),
-                maker_ta_a.clone(),
-                escrow.clone(),
-            ],
+        escrow_data.release(bump, token_program, mint_a, maker, escrow, vault, maker_ta_a, amount, remaining_accounts)?;
+
+        // Close the vault & escrow
+        escrow_data.close_vault(bump, token_program, mint_a, maker, escrow, vault)?;
+        close_program_account(maker, escrow)
+    }
+
+    #[inline]
+    pub fn check_dispute(&self) -> ProgramResult {
+        // A disputed escrow is frozen until the arbiter resolves it
+        if self.disputed != 0 {
+            return Err(EscrowError::EscrowDisputed.into());
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub fn check_arbiter(&self, now: i64) -> ProgramResult {
+        // The maker cannot pull funds or change terms under the taker until an arbitrated escrow expires
+        if self.arbiter.ne(&Pubkey::default()) && !self.is_expired(now) {
+            return Err(EscrowError::ArbitratedEscrow.into());
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub fn dispute(mut escrow_data: Escrow, party: &AccountInfo, escrow: &AccountInfo) -> ProgramResult {
+        // Check the escrow has an arbiter to resolve the dispute
+        if escrow_data.arbiter.eq(&Pubkey::default()) {
+            return Err(EscrowError::InvalidArbiter.into());
+        }
+
+        // Check the signer is the maker or the taker
+        if escrow_data.maker.ne(party.key) && escrow_data.taker.ne(party.key) {
+            return Err(EscrowError::InvalidAuthority.into());
+        }
+
+        // Freeze the escrow
+        escrow_data.disputed = 1;
+        escrow_data.save(escrow)
+    }
+
+    #[inline]
+    #[allow(clippy::too_many_arguments)]
+    pub fn resolve<'a>(
+        escrow_data: Escrow,
+        bump: u8,
+        token_program: &Pubkey,
+        maker_amount: u64,
+        taker_amount: u64,
+        mint_a: &AccountInfo<'a>,
+        maker: &AccountInfo<'a>,
+        escrow: &AccountInfo<'a>,
+        vault: &AccountInfo<'a>,
+        maker_ta_a: &AccountInfo<'a>,
+        taker_ta_a: &AccountInfo<'a>,
+        remaining_accounts: &[AccountInfo<'a>],
+    ) -> ProgramResult {
+
+        // Check the escrow is disputed
+        if escrow_data.disputed == 0 {
+            return Err(EscrowError::EscrowNotDisputed.into());
+        }
+
+        // Check mints match
+        if mint_a.key.ne(&escrow_data.mint_a) {
+            return Err(EscrowError::MintMismatch.into());
+        }
+
+        // Check the split covers the whole vault
+        let amount = escrow_data.get_vault_amount(escrow, vault)?;
+        if maker_amount.checked_add(taker_amount) != Some(amount) {
+            return Err(EscrowError::InvalidAmount.into());
+        }
+
+        // Make sure each share goes to its party
+        escrow_data.check_recipient(maker_ta_a, &escrow_data.maker, EscrowError::InvalidMakerTokenAccount)?;
+        escrow_data.check_recipient(taker_ta_a, &escrow_data.taker, EscrowError::InvalidTaker)?;
+
+        // Log each party's share of mint A
+        escrow_data.event(EVENT_RESOLVE, escrow.key, taker_amount, 0).emit(remaining_accounts)?;
+        escrow_data.event(EVENT_RESOLVE_MAKER, escrow.key, maker_amount, 0).emit(remaining_accounts)?;
+
+        // Split the vault funds
+        if maker_amount > 0 {
+            escrow_data.release(bump, token_program, mint_a, maker, escrow, vault, maker_ta_a, maker_amount, remaining_accounts)?;
+        }
+        if taker_amount > 0 {
+            escrow_data.release(bump, token_program, mint_a, maker, escrow, vault, taker_ta_a, taker_amount, remaining_accounts)?;
+        }
+
+        // Close the vault & escrow
+        escrow_data.close_all(bump, token_program, mint_a, maker, escrow, vault)
+    }
+
+    #[inline]
+    pub fn check_recipient(&self, recipient: &AccountInfo, owner: &Pubkey, error: EscrowError) -> ProgramResult {
+        // Native SOL is paid to the wallet itself, tokens to a token account it owns
+        let recipient_owner = if self.is_native_a() {
+            *recipient.key
+        } else {
+            get_token_account_owner(recipient)?
+        };
+        if recipient_owner.ne(owner) {
+            return Err(error.into());
+        }
+        Ok(())
+    }
+
+    #[inline]
+    #[allow(clippy::too_many_arguments)]
+    pub fn release<'a>(
+        &self,
+        bump: u8,
+        token_program: &Pubkey,
+        mint_a: &AccountInfo<'a>,
+        maker: &AccountInfo<'a>,
+        escrow: &AccountInfo<'a>,
+        vault: &AccountInfo<'a>,
+        destination: &AccountInfo<'a>,
+        amount: u64,
+        remaining_accounts: &[AccountInfo<'a>],
+    ) -> ProgramResult {
+        if self.is_native_a() {
+            // Release SOL held above rent in the escrow
+            **escrow.lamports.borrow_mut() -= amount;
+            **destination.lamports.borrow_mut() += amount;
+            return Ok(());
+        }
+
+        // Check token program
+        check_token_program(token_program)?;
+
+        // Release token A from the vault
+        transfer(
+            token_program,
+            vault,
+            mint_a,
+            destination,
+            escrow,
+            amount,
             &[&[
-                b"escrow",
+                ESCROW_SEED,
                 maker.key.as_ref(),
-                escrow_data.seed.to_le_bytes().as_ref(),
+                self.seed.to_le_bytes().as_ref(),
                 &[bump],
             ]],
-        )?;
+            remaining_accounts,
+        )
+    }
+
+    #[inline]
+    pub fn close_all<'a>(
+        &self,
+        bump: u8,
+        token_program: &Pubkey,
+        mint_a: &AccountInfo<'a>,
+        maker: &AccountInfo<'a>,
+        escrow: &AccountInfo<'a>,
+        vault: &AccountInfo<'a>,
+    ) -> ProgramResult {
+        // Native SOL escrows have no vault
+        if !self.is_native_a() {
+            self.close_vault(bump, token_program, mint_a, maker, escrow, vault)?;
+        }
+        close_program_account(maker, escrow)
+    }
+
+    #[inline]
+    pub fn close_vault<'a>(
+        &self,
+        bump: u8,
+        token_program: &Pubkey,
+        mint_a: &AccountInfo<'a>,
+        maker: &AccountInfo<'a>,
+        escrow: &AccountInfo<'a>,
+        vault: &AccountInfo<'a>,
+    ) -> ProgramResult {
+        // Harvest transfer fees withheld in the vault to the mint, as they block closing it
+        if get_withheld_amount(vault)? > 0 {
+            invoke(
+                &harvest_withheld_tokens_to_mint(token_program, mint_a.key, &[vault.key])?,
+                &[
+                    mint_a.clone(),
+                    vault.clone(),
+                ],
+            )?;
+        }
 
         // Close the vault
         invoke_signed(
@@ -321,20 +1272,305 @@ impl Escrow {
                 escrow.clone()
             ],
             &[&[
-                b"escrow",
+                ESCROW_SEED,
                 maker.key.as_ref(),
-                escrow_data.seed.to_le_bytes().as_ref(),
+                self.seed.to_le_bytes().as_ref(),
                 &[bump],
             ]],
+        )
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            discriminator: CONFIG_DISCRIMINATOR,
+            version: CONFIG_VERSION,
+            ..Zeroable::zeroed()
+        }
+    }
+}
+
+impl Config {
+    #[inline]
+    pub fn load(config: &AccountInfo) -> Result<Option<Config>, ProgramError> {
+        // Check config address
+        if config.key.ne(&CONFIG_ADDRESS) {
+            return Err(EscrowError::InvalidConfigAddress.into());
+        }
+
+        // No fee is charged until the config is initialized
+        if config.owner.ne(&crate::ID) {
+            return Ok(None);
+        }
+
+        // Check account kind and layout version
+        let data = config.try_borrow_data()?;
+        let config_data = bytemuck::try_from_bytes::<Config>(&data)
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        if config_data.discriminator != CONFIG_DISCRIMINATOR {
+            return Err(EscrowError::InvalidDiscriminator.into());
+        }
+        if config_data.version != CONFIG_VERSION {
+            return Err(EscrowError::UnsupportedVersion.into());
+        }
+
+        Ok(Some(*config_data))
+    }
+
+    #[inline]
+    pub fn get(config: &AccountInfo) -> Result<Config, ProgramError> {
+        // Admin instructions require an initialized config
+        Config::load(config)?.ok_or(ProgramError::UninitializedAccount)
+    }
+
+    #[inline]
+    pub fn is_paused(&self) -> bool {
+        self.flags & CONFIG_FLAG_PAUSED != 0
+    }
+
+    #[inline]
+    pub fn is_fee_on_a(&self) -> bool {
+        self.flags & CONFIG_FLAG_FEE_ON_A != 0
+    }
+
+    #[inline]
+    pub fn fee(&self, amount: u64) -> u64 {
+        // Rounded down in favour of the traders
+        (amount as u128 * self.fee_bps as u128 / BPS_DENOMINATOR as u128) as u64
+    }
+
+    #[inline]
+    pub fn check_treasury(&self, is_native: bool, treasury: &AccountInfo) -> ProgramResult {
+        // Native SOL fees are paid to the treasury itself, token fees to a token account it owns
+        let owner = if is_native {
+            *treasury.key
+        } else {
+            get_token_account_owner(treasury)?
+        };
+        if owner.ne(&self.treasury) {
+            return Err(EscrowError::InvalidTreasury.into());
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub fn init<'a>(
+        config_data: Config,
+        payer: &AccountInfo<'a>,
+        config: &AccountInfo<'a>,
+    ) -> ProgramResult {
+        // Check config address
+        if config.key.ne(&CONFIG_ADDRESS) {
+            return Err(EscrowError::InvalidConfigAddress.into());
+        }
+
+        // Check config settings
+        config_data.check_settings()?;
+
+        let space = core::mem::size_of::<Config>();
+        let rent = Rent::get()?.minimum_balance(space);
+
+        // Create the Config Account
+        invoke_signed(
+            &create_account(
+                payer.key,
+                config.key,
+                rent,
+                space as u64,
+                &crate::ID,
+            ),
+            &[
+                payer.clone(),
+                config.clone()
+            ],
+            &[&[CONFIG_SEED, &[CONFIG_BUMP]]],
         )?;
 
-        // Close the escrow
-        let balance = escrow.lamports();
-        escrow.realloc(0, false)?;
-        **escrow.lamports.borrow_mut() = 0;
-        **maker.lamports.borrow_mut() += balance;
-        escrow.assign(&Pubkey::default());
+        // Create the config
+        config_data.save(config)
+    }
+
+    #[inline]
+    pub fn check_settings(&self) -> ProgramResult {
+        // Check flags are known and fee is at most 100%
+        if self.flags & !VALID_CONFIG_FLAGS != 0 {
+            return Err(EscrowError::InvalidFlags.into());
+        }
+        if self.fee_bps > BPS_DENOMINATOR {
+            return Err(EscrowError::InvalidFee.into());
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub fn check_admin(&self, admin: &AccountInfo) -> ProgramResult {
+        // Check admin signed
+        if self.admin.ne(admin.key) {
+            return Err(EscrowError::InvalidAuthority.into());
+        }
+        if !admin.is_signer {
+            return Err(EscrowError::MissingSigner.into());
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub fn update(
+        mut config_data: Config,
+        fee_bps: u16,
+        flags: u8,
+        treasury: &Pubkey,
+        pending_admin: &Pubkey,
+        config: &AccountInfo,
+    ) -> ProgramResult {
+        // Replace the settings, nominating a new admin who must accept before taking over
+        config_data.fee_bps = fee_bps;
+        config_data.flags = flags;
+        config_data.treasury = *treasury;
+        config_data.pending_admin = *pending_admin;
+
+        // Check config settings
+        config_data.check_settings()?;
+
+        config_data.save(config)
+    }
+
+    #[inline]
+    pub fn accept_admin(
+        mut config_data: Config,
+        pending_admin: &AccountInfo,
+        config: &AccountInfo,
+    ) -> ProgramResult {
+        // Check the signer was nominated
+        if config_data.pending_admin.eq(&Pubkey::default()) || config_data.pending_admin.ne(pending_admin.key) {
+            return Err(EscrowError::InvalidAuthority.into());
+        }
+        if !pending_admin.is_signer {
+            return Err(EscrowError::MissingSigner.into());
+        }
+
+        // Hand over admin authority
+        config_data.admin = config_data.pending_admin;
+        config_data.pending_admin = Pubkey::default();
+
+        config_data.save(config)
+    }
+
+    #[inline]
+    pub fn save(&self, config: &AccountInfo) -> ProgramResult {
+        *bytemuck::try_from_bytes_mut::<Config>(*config.try_borrow_mut_data()?)
+            .map_err(|_| ProgramError::InvalidAccountData)? = *self;
+        Ok(())
+    }
+}
+
+impl Default for MintEntry {
+    fn default() -> Self {
+        Self {
+            discriminator: MINT_ENTRY_DISCRIMINATOR,
+            version: MINT_ENTRY_VERSION,
+            ..Zeroable::zeroed()
+        }
+    }
+}
+
+impl MintEntry {
+    #[inline]
+    pub fn get_status(mint: &Pubkey, entry: &AccountInfo) -> Result<u8, ProgramError> {
+        // Mints without an entry are unlisted, with no stored bump to check their entry's PDA against
+        if entry.owner.ne(&crate::ID) {
+            check_eq_program_derived_address_and_get_bump(&[MINT_ENTRY_SEED, mint.as_ref()], &crate::ID, entry.key, EscrowError::InvalidMintEntryAddress)?;
+            return Ok(MINT_STATUS_UNLISTED);
+        }
+
+        // Check account kind and layout version
+        let data = entry.try_borrow_data()?;
+        let entry_data = bytemuck::try_from_bytes::<MintEntry>(&data)
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        if entry_data.discriminator != MINT_ENTRY_DISCRIMINATOR {
+            return Err(EscrowError::InvalidDiscriminator.into());
+        }
+        if entry_data.version != MINT_ENTRY_VERSION {
+            return Err(EscrowError::UnsupportedVersion.into());
+        }
+
+        // Check PDA of mint entry with the stored bump
+        check_eq_program_address(&[MINT_ENTRY_SEED, mint.as_ref(), &[entry_data.bump]], &crate::ID, entry.key, EscrowError::InvalidMintEntryAddress)?;
+
+        Ok(entry_data.status)
+    }
+
+    #[inline]
+    pub fn check_listable(config_data: Option<Config>, mint: &Pubkey, entry: &AccountInfo) -> ProgramResult {
+        // Any mint may be listed unless a registry is configured
+        let Some(config_data) = config_data.filter(|config_data| config_data.flags & (CONFIG_FLAG_ALLOWLIST | CONFIG_FLAG_DENYLIST) != 0) else {
+            return Ok(());
+        };
+
+        let status = MintEntry::get_status(mint, entry)?;
+
+        // Check mint is allowed or not denied
+        if (config_data.flags & CONFIG_FLAG_ALLOWLIST != 0 && status != MINT_STATUS_ALLOWED)
+            || (config_data.flags & CONFIG_FLAG_DENYLIST != 0 && status == MINT_STATUS_DENIED)
+        {
+            return Err(EscrowError::MintNotAllowed.into());
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub fn set_status<'a>(
+        status: u8,
+        mint: &Pubkey,
+        admin: &AccountInfo<'a>,
+        entry: &AccountInfo<'a>,
+    ) -> ProgramResult {
+        // Check status is known
+        if status > MINT_STATUS_DENIED {
+            return Err(EscrowError::InvalidMintStatus.into());
+        }
+
+        // Check PDA of mint entry and get bump
+        let bump = check_eq_program_derived_address_and_get_bump(&[MINT_ENTRY_SEED, mint.as_ref()], &crate::ID, entry.key, EscrowError::InvalidMintEntryAddress)?;
+
+        // Unlisting closes the entry
+        if status == MINT_STATUS_UNLISTED {
+            if entry.owner.ne(&crate::ID) {
+                return Ok(());
+            }
+            return close_program_account(admin, entry);
+        }
+
+        // Create the Mint Entry Account on first listing
+        if entry.owner.ne(&crate::ID) {
+            let space = core::mem::size_of::<MintEntry>();
+            let rent = Rent::get()?.minimum_balance(space);
+
+            invoke_signed(
+                &create_account(
+                    admin.key,
+                    entry.key,
+                    rent,
+                    space as u64,
+                    &crate::ID,
+                ),
+                &[
+                    admin.clone(),
+                    entry.clone()
+                ],
+                &[&[MINT_ENTRY_SEED, mint.as_ref(), &[bump]]],
+            )?;
+        }
 
+        // Set the mint status
+        *bytemuck::try_from_bytes_mut::<MintEntry>(*entry.try_borrow_mut_data()?)
+            .map_err(|_| ProgramError::InvalidAccountData)? = MintEntry {
+            status,
+            bump,
+            mint: *mint,
+            ..Default::default()
+        };
         Ok(())
     }
 }
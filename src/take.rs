@@ -1,13 +1,26 @@
 use solana_program::{
     account_info::AccountInfo,
+    clock::Clock,
     entrypoint::ProgramResult,
     program_error::ProgramError,
+    sysvar::Sysvar,
 };
-use crate::Escrow;
+use crate::{error::EscrowError, Config, Escrow, TakePartial};
 
 /// Taker pays funds to Maker and claims funds in Vault
 pub fn process(accounts: &[AccountInfo<'_>]) -> ProgramResult {
-    let [taker, maker, mint_a, mint_b, taker_ta_a, taker_ta_b, maker_ta_b, escrow, vault, token_program, _system_program] =
+    fill(accounts, None)
+}
+
+/// Taker pays a pro-rata share of funds to Maker and claims part of the funds in Vault
+pub fn process_partial(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult {
+    let TakePartial { amount } = TakePartial::try_from(data)?;
+
+    fill(accounts, Some(amount))
+}
+
+fn fill(accounts: &[AccountInfo<'_>], amount: Option<u64>) -> ProgramResult {
+    let [taker, maker, mint_a, mint_b, taker_ta_a, taker_ta_b, maker_ta_b, escrow, vault, token_program, _system_program, config, treasury, remaining_accounts @ ..] =
         accounts
     else {
         return Err(ProgramError::NotEnoughAccountKeys);
@@ -16,6 +29,22 @@ pub fn process(accounts: &[AccountInfo<'_>]) -> ProgramResult {
     // Check & Get escrow account data and bump
     let (escrow_data, bump) = Escrow::get_data_and_bump(maker.key, escrow)?;
 
-    // Take: Claim token A to taker, Transfer token B to maker, Close the vault & escrow
-    Escrow::take(escrow_data, bump, token_program.key, mint_a, mint_b, maker, taker, escrow, vault, maker_ta_b, taker_ta_a, taker_ta_b)
+    // Make sure the escrow is not frozen by a dispute
+    escrow_data.check_dispute()?;
+
+    // Make sure the escrow has not expired
+    if escrow_data.is_expired(Clock::get()?.unix_timestamp) {
+        return Err(EscrowError::EscrowExpired.into());
+    }
+
+    // Get protocol fee settings, if configured
+    let config_data = Config::load(config)?;
+
+    // Make sure trading is not paused
+    if config_data.is_some_and(|config_data| config_data.is_paused()) {
+        return Err(EscrowError::ProtocolPaused.into());
+    }
+
+    // Take: Claim token A to taker, Transfer token B to maker, Skim the protocol fee, Close the vault & escrow once fully filled
+    Escrow::take(escrow_data, bump, token_program.key, mint_a, mint_b, maker, taker, escrow, vault, maker_ta_b, taker_ta_a, taker_ta_b, config_data, treasury, amount, remaining_accounts)
 }
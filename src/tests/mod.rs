@@ -5,17 +5,42 @@ use mollusk_svm::{
     result::ProgramResult,
     Mollusk,
 };
-use solana_program::instruction::AccountMeta;
 use solana_sdk::{
-    account::{AccountSharedData, WritableAccount},
-    instruction::Instruction,
+    account::{AccountSharedData, ReadableAccount, WritableAccount},
+    instruction::{AccountMeta, Instruction},
     program_option::COption,
+    program_error::ProgramError,
     program_pack::Pack,
     pubkey::Pubkey,
 };
+use spl_tlv_account_resolution::{account::ExtraAccountMeta, seeds::Seed, state::ExtraAccountMetaList};
 use spl_token::state::AccountState;
+use spl_token_2022::extension::{
+    immutable_owner::ImmutableOwner, interest_bearing_mint::InterestBearingConfig,
+    metadata_pointer::MetadataPointer,
+    transfer_fee::{TransferFee, TransferFeeAmount, TransferFeeConfig},
+    transfer_hook::{TransferHook, TransferHookAccount},
+    BaseStateWithExtensionsMut, ExtensionType, StateWithExtensions, StateWithExtensionsMut,
+};
+
+use spl_transfer_hook_interface::{get_extra_account_metas_address, instruction::ExecuteInstruction};
 
-use crate::Escrow;
+use crate::{
+    client::{
+        accept_admin_instruction, add_event_accounts, add_transfer_hook_accounts, approve_instruction, claim_instruction, deposit_instruction, dispute_instruction, expire_instruction, find_mint_entry_address,
+        initialize_config_instruction, make_instruction, migrate_instruction, refund_instruction,
+        resolve_instruction, set_mint_status_instruction, take_instruction, take_partial_instruction,
+        update_config_instruction, update_instruction, withdraw_instruction,
+    },
+    error::EscrowError,
+    events::{EscrowEvent, EVENT_AUTHORITY_ADDRESS, EVENT_AUTHORITY_BUMP, EVENT_TAKE},
+    state::{
+        Config, EscrowV1, MintEntry, PriceFeed, CONFIG_ADDRESS, CONFIG_BUMP, CONFIG_FLAG_ALLOWLIST,
+        CONFIG_FLAG_DENYLIST, CONFIG_FLAG_PAUSED, ESCROW_VERSION, FLAG_NATIVE_A, FLAG_NATIVE_B,
+        FLAG_RECEIVE_NET_OF_FEE, FLAG_SCALE_RECEIVE, MINT_STATUS_ALLOWED, MINT_STATUS_DENIED,
+    },
+    Escrow, InitializeConfig, Make, Resolve, Update, UpdateConfig, UPDATE_FLAG_SET_EXPIRY,
+};
 
 #[test]
 fn make() {
@@ -110,32 +135,10 @@ fn make() {
     )
     .unwrap();
 
-    let mut vault_account = AccountSharedData::new(
-        mollusk
-            .sysvars
-            .rent
-            .minimum_balance(spl_token::state::Account::LEN),
-        spl_token::state::Account::LEN,
-        &token_program,
-    );
-    solana_program::program_pack::Pack::pack(
-        spl_token::state::Account {
-            mint: mint_a,
-            owner: escrow,
-            amount: 0,
-            delegate: COption::None,
-            state: AccountState::Initialized,
-            is_native: COption::None,
-            delegated_amount: 0,
-            close_authority: COption::None,
-        },
-        vault_account.data_as_mut_slice(),
-    )
-    .unwrap();
-
     let escrow_account = AccountSharedData::new(0, 0, &Pubkey::default());
+    let vault_account = AccountSharedData::new(0, 0, &Pubkey::default());
 
-    // Create our instruction
+    // Create our instruction, with the optional terms after seed, amount and receive left zeroed
     let instruction = Instruction::new_with_bytes(
         crate::ID,
         &[
@@ -143,6 +146,7 @@ fn make() {
             &seed.to_le_bytes()[..],
             &100000u64.to_le_bytes()[..],
             &100000u64.to_le_bytes()[..],
+            &[0; mem::size_of::<Make>() - 24][..],
         ]
         .concat(),
         vec![
@@ -154,6 +158,9 @@ fn make() {
             AccountMeta::new(vault, false),
             AccountMeta::new_readonly(token_program, false),
             AccountMeta::new_readonly(system_program, false),
+            AccountMeta::new_readonly(CONFIG_ADDRESS, false),
+            AccountMeta::new_readonly(find_mint_entry_address(&mint_a).0, false),
+            AccountMeta::new_readonly(find_mint_entry_address(&mint_b).0, false),
         ],
     );
 
@@ -171,9 +178,19 @@ fn make() {
             (vault, vault_account),
             (token_program, token_program_account),
             (system_program, system_program_account),
+            (CONFIG_ADDRESS, AccountSharedData::default()),
+            (find_mint_entry_address(&mint_a).0, AccountSharedData::default()),
+            (find_mint_entry_address(&mint_b).0, AccountSharedData::default()),
         ],
     );
-    assert!(matches!(result.program_result, ProgramResult::Success))
+    assert!(matches!(result.program_result, ProgramResult::Success));
+
+    // The vault is created by the program, owned by the escrow and funded by the maker
+    let vault_data =
+        spl_token::state::Account::unpack(resulting_account(&result, &vault).data()).unwrap();
+    assert_eq!(vault_data.owner, escrow);
+    assert_eq!(vault_data.mint, mint_a);
+    assert_eq!(vault_data.amount, 100_000);
 }
 
 #[test]
@@ -198,12 +215,12 @@ fn refund() {
         &mint_a,
         &token_program,
     );
-    let escrow = Pubkey::find_program_address(
+    let (escrow, bump) = Pubkey::find_program_address(
         &[b"escrow", maker.as_ref(), &seed.to_le_bytes()],
         &crate::ID,
-    )
-    .0;
-    let vault = Pubkey::find_program_address(&[b"vault", escrow.as_ref()], &crate::ID).0;
+    );
+    let (vault, vault_bump) =
+        Pubkey::find_program_address(&[b"vault", escrow.as_ref()], &crate::ID);
 
     // Fill out our account data
     let mut mint_a_account = AccountSharedData::new(
@@ -306,6 +323,9 @@ fn refund() {
         mint_a,
         mint_b,
         receive: 100_000,
+        bump,
+        vault_bump,
+        ..Default::default()
     }));
 
     // Create our instruction
@@ -314,7 +334,7 @@ fn refund() {
         &[0x02],
         vec![
             AccountMeta::new(maker, true),
-            AccountMeta::new_readonly(mint_a, false),
+            AccountMeta::new(mint_a, false),
             AccountMeta::new(maker_ta_a, false),
             AccountMeta::new(escrow, false),
             AccountMeta::new(vault, false),
@@ -374,12 +394,12 @@ fn take() {
         &mint_b,
         &token_program,
     );
-    let escrow = Pubkey::find_program_address(
+    let (escrow, bump) = Pubkey::find_program_address(
         &[b"escrow", maker.as_ref(), &seed.to_le_bytes()],
         &crate::ID,
-    )
-    .0;
-    let vault = Pubkey::find_program_address(&[b"vault", escrow.as_ref()], &crate::ID).0;
+    );
+    let (vault, vault_bump) =
+        Pubkey::find_program_address(&[b"vault", escrow.as_ref()], &crate::ID);
 
     // Fill out our account data
     let mut mint_a_account = AccountSharedData::new(
@@ -479,7 +499,7 @@ fn take() {
     solana_program::program_pack::Pack::pack(
         spl_token::state::Account {
             mint: mint_b,
-            owner: taker,
+            owner: maker,
             amount: 0,
             delegate: COption::None,
             state: AccountState::Initialized,
@@ -528,8 +548,13 @@ fn take() {
         mint_a,
         mint_b,
         receive: 100_000,
+        bump,
+        vault_bump,
+        ..Default::default()
     }));
 
+    let treasury = Pubkey::new_from_array([0x08; 32]);
+
     // Create our instruction
     let instruction = Instruction::new_with_bytes(
         crate::ID,
@@ -537,7 +562,7 @@ fn take() {
         vec![
             AccountMeta::new(taker, true),
             AccountMeta::new(maker, false),
-            AccountMeta::new_readonly(mint_a, false),
+            AccountMeta::new(mint_a, false),
             AccountMeta::new_readonly(mint_b, false),
             AccountMeta::new(taker_ta_a, false),
             AccountMeta::new(taker_ta_b, false),
@@ -546,6 +571,8 @@ fn take() {
             AccountMeta::new(vault, false),
             AccountMeta::new_readonly(token_program, false),
             AccountMeta::new_readonly(system_program, false),
+            AccountMeta::new_readonly(CONFIG_ADDRESS, false),
+            AccountMeta::new(treasury, false),
         ],
     );
 
@@ -569,8 +596,3912 @@ fn take() {
             (vault, vault_account),
             (token_program, token_program_account),
             (system_program, system_program_account),
+            (CONFIG_ADDRESS, AccountSharedData::default()),
+            (treasury, AccountSharedData::default()),
+        ],
+    );
+
+    assert!(matches!(result.program_result, ProgramResult::Success));
+}
+
+#[test]
+fn instruction_wire_format() {
+    // Set our seed
+    let seed: u64 = 1337;
+
+    // Accounts
+    let maker = Pubkey::new_from_array([0x01; 32]);
+    let mint_a = Pubkey::new_from_array([0x02; 32]);
+    let mint_b = Pubkey::new_from_array([0x03; 32]);
+    let taker = Pubkey::new_from_array([0x04; 32]);
+    let maker_ta_a = Pubkey::new_from_array([0x05; 32]);
+    let taker_ta_a = Pubkey::new_from_array([0x06; 32]);
+    let taker_ta_b = Pubkey::new_from_array([0x07; 32]);
+    let maker_ta_b = Pubkey::new_from_array([0x08; 32]);
+    let treasury = Pubkey::new_from_array([0x09; 32]);
+    let admin = Pubkey::new_from_array([0x0a; 32]);
+    let token_program = spl_token::ID;
+    let system_program = solana_sdk::system_program::ID;
+    let escrow = Pubkey::find_program_address(
+        &[b"escrow", maker.as_ref(), &seed.to_le_bytes()],
+        &crate::ID,
+    )
+    .0;
+    let vault = Pubkey::find_program_address(&[b"vault", escrow.as_ref()], &crate::ID).0;
+    let mint_a_entry = Pubkey::find_program_address(&[b"mint", mint_a.as_ref()], &crate::ID).0;
+    let mint_b_entry = Pubkey::find_program_address(&[b"mint", mint_b.as_ref()], &crate::ID).0;
+    let program_data =
+        Pubkey::find_program_address(&[crate::ID.as_ref()], &solana_sdk::bpf_loader_upgradeable::ID).0;
+    let refund_accounts = |maker_is_signer: bool, recipient: Pubkey| {
+        vec![
+            AccountMeta::new(maker, maker_is_signer),
+            AccountMeta::new(mint_a, false),
+            AccountMeta::new(recipient, false),
+            AccountMeta::new(escrow, false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new_readonly(token_program, false),
+            AccountMeta::new_readonly(system_program, false),
+        ]
+    };
+    let take_accounts = vec![
+        AccountMeta::new(taker, true),
+        AccountMeta::new(maker, false),
+        AccountMeta::new(mint_a, false),
+        AccountMeta::new_readonly(mint_b, false),
+        AccountMeta::new(taker_ta_a, false),
+        AccountMeta::new(taker_ta_b, false),
+        AccountMeta::new(maker_ta_b, false),
+        AccountMeta::new(escrow, false),
+        AccountMeta::new(vault, false),
+        AccountMeta::new_readonly(token_program, false),
+        AccountMeta::new_readonly(system_program, false),
+        AccountMeta::new_readonly(CONFIG_ADDRESS, false),
+        AccountMeta::new(treasury, false),
+    ];
+
+    // Each builder matches the instruction assembled by hand from the documented wire format
+    let cases = vec![
+        (
+            make_instruction(
+                &maker,
+                &mint_a,
+                &mint_b,
+                &maker_ta_a,
+                &token_program,
+                Make {
+                    seed,
+                    amount: 100_000,
+                    receive: 200_000,
+                    ..Default::default()
+                },
+            ),
+            Instruction::new_with_bytes(
+                crate::ID,
+                &[
+                    &[0x00],
+                    &seed.to_le_bytes()[..],
+                    &100000u64.to_le_bytes()[..],
+                    &200000u64.to_le_bytes()[..],
+                    &[0; mem::size_of::<Make>() - 24][..],
+                ]
+                .concat(),
+                vec![
+                    AccountMeta::new(maker, true),
+                    AccountMeta::new_readonly(mint_a, false),
+                    AccountMeta::new_readonly(mint_b, false),
+                    AccountMeta::new(maker_ta_a, false),
+                    AccountMeta::new(escrow, false),
+                    AccountMeta::new(vault, false),
+                    AccountMeta::new_readonly(token_program, false),
+                    AccountMeta::new_readonly(system_program, false),
+                    AccountMeta::new_readonly(CONFIG_ADDRESS, false),
+                    AccountMeta::new_readonly(mint_a_entry, false),
+                    AccountMeta::new_readonly(mint_b_entry, false),
+                ],
+            ),
+        ),
+        (
+            take_instruction(&taker, &maker, &mint_a, &mint_b, &taker_ta_a, &taker_ta_b, &maker_ta_b, &treasury, &token_program, seed),
+            Instruction::new_with_bytes(crate::ID, &[0x01], take_accounts.clone()),
+        ),
+        (
+            refund_instruction(&maker, &mint_a, &maker_ta_a, &token_program, seed),
+            Instruction::new_with_bytes(crate::ID, &[0x02], refund_accounts(true, maker_ta_a)),
+        ),
+        (
+            take_partial_instruction(&taker, &maker, &mint_a, &mint_b, &taker_ta_a, &taker_ta_b, &maker_ta_b, &treasury, &token_program, seed, 25_000),
+            Instruction::new_with_bytes(crate::ID, &[&[0x03], &25000u64.to_le_bytes()[..]].concat(), take_accounts),
+        ),
+        (
+            expire_instruction(&maker, &mint_a, &maker_ta_a, &token_program, seed),
+            Instruction::new_with_bytes(crate::ID, &[0x04], refund_accounts(false, maker_ta_a)),
+        ),
+        (
+            migrate_instruction(&admin, &maker, seed),
+            Instruction::new_with_bytes(
+                crate::ID,
+                &[0x05],
+                vec![
+                    AccountMeta::new(admin, true),
+                    AccountMeta::new_readonly(maker, false),
+                    AccountMeta::new(escrow, false),
+                    AccountMeta::new_readonly(system_program, false),
+                ],
+            ),
+        ),
+        (
+            update_instruction(
+                &maker,
+                &mint_b,
+                seed,
+                Update {
+                    receive: 200_000,
+                    expires_at: 2_000,
+                    flags: UPDATE_FLAG_SET_EXPIRY,
+                    ..Default::default()
+                },
+            ),
+            Instruction::new_with_bytes(
+                crate::ID,
+                &[&[0x06], &200000u64.to_le_bytes()[..], &2000i64.to_le_bytes()[..], &[0x01], &[0; 7]].concat(),
+                vec![
+                    AccountMeta::new_readonly(maker, true),
+                    AccountMeta::new_readonly(mint_b, false),
+                    AccountMeta::new(escrow, false),
+                    AccountMeta::new_readonly(CONFIG_ADDRESS, false),
+                    AccountMeta::new_readonly(mint_b_entry, false),
+                ],
+            ),
+        ),
+        (
+            deposit_instruction(&maker, &mint_a, &maker_ta_a, &token_program, seed, 50_000),
+            Instruction::new_with_bytes(crate::ID, &[&[0x07], &50000u64.to_le_bytes()[..]].concat(), refund_accounts(true, maker_ta_a)),
+        ),
+        (
+            withdraw_instruction(&maker, &mint_a, &maker_ta_a, &token_program, seed, 50_000),
+            Instruction::new_with_bytes(crate::ID, &[&[0x08], &50000u64.to_le_bytes()[..]].concat(), refund_accounts(true, maker_ta_a)),
+        ),
+        (
+            initialize_config_instruction(
+                &admin,
+                InitializeConfig {
+                    fee_bps: 30,
+                    flags: 1,
+                    treasury,
+                    admin,
+                    ..Default::default()
+                },
+            ),
+            Instruction::new_with_bytes(
+                crate::ID,
+                &[&[0x09], &30u16.to_le_bytes()[..], &[1, 0], treasury.as_ref(), admin.as_ref()].concat(),
+                vec![
+                    AccountMeta::new(admin, true),
+                    AccountMeta::new(CONFIG_ADDRESS, false),
+                    AccountMeta::new_readonly(program_data, false),
+                    AccountMeta::new_readonly(system_program, false),
+                ],
+            ),
+        ),
+        (
+            update_config_instruction(
+                &admin,
+                UpdateConfig {
+                    fee_bps: 30,
+                    flags: 1,
+                    treasury,
+                    pending_admin: maker,
+                    ..Default::default()
+                },
+            ),
+            Instruction::new_with_bytes(
+                crate::ID,
+                &[&[0x0a], &30u16.to_le_bytes()[..], &[1, 0], treasury.as_ref(), maker.as_ref()].concat(),
+                vec![
+                    AccountMeta::new_readonly(admin, true),
+                    AccountMeta::new(CONFIG_ADDRESS, false),
+                ],
+            ),
+        ),
+        (
+            accept_admin_instruction(&admin),
+            Instruction::new_with_bytes(
+                crate::ID,
+                &[0x0b],
+                vec![
+                    AccountMeta::new_readonly(admin, true),
+                    AccountMeta::new(CONFIG_ADDRESS, false),
+                ],
+            ),
+        ),
+        (
+            set_mint_status_instruction(&admin, &mint_a, MINT_STATUS_ALLOWED),
+            Instruction::new_with_bytes(
+                crate::ID,
+                &[0x0c, 0x01],
+                vec![
+                    AccountMeta::new(admin, true),
+                    AccountMeta::new_readonly(CONFIG_ADDRESS, false),
+                    AccountMeta::new_readonly(mint_a, false),
+                    AccountMeta::new(mint_a_entry, false),
+                    AccountMeta::new_readonly(system_program, false),
+                ],
+            ),
+        ),
+        (
+            claim_instruction(&maker, &mint_a, &taker_ta_a, &token_program, seed, [0x0f; 32]),
+            Instruction::new_with_bytes(crate::ID, &[&[0x0e], &[0x0f; 32][..]].concat(), refund_accounts(false, taker_ta_a)),
+        ),
+        (
+            approve_instruction(&admin, &maker, seed),
+            Instruction::new_with_bytes(
+                crate::ID,
+                &[0x0f],
+                vec![
+                    AccountMeta::new_readonly(admin, true),
+                    AccountMeta::new_readonly(maker, false),
+                    AccountMeta::new(escrow, false),
+                ],
+            ),
+        ),
+        (
+            dispute_instruction(&taker, &maker, seed),
+            Instruction::new_with_bytes(
+                crate::ID,
+                &[0x10],
+                vec![
+                    AccountMeta::new_readonly(taker, true),
+                    AccountMeta::new_readonly(maker, false),
+                    AccountMeta::new(escrow, false),
+                ],
+            ),
+        ),
+        (
+            resolve_instruction(
+                &admin,
+                &maker,
+                &mint_a,
+                &maker_ta_a,
+                &taker_ta_a,
+                &token_program,
+                seed,
+                Resolve {
+                    maker_amount: 40_000,
+                    taker_amount: 60_000,
+                },
+            ),
+            Instruction::new_with_bytes(
+                crate::ID,
+                &[&[0x11], &40000u64.to_le_bytes()[..], &60000u64.to_le_bytes()[..]].concat(),
+                vec![
+                    AccountMeta::new_readonly(admin, true),
+                    AccountMeta::new(maker, false),
+                    AccountMeta::new(mint_a, false),
+                    AccountMeta::new(maker_ta_a, false),
+                    AccountMeta::new(taker_ta_a, false),
+                    AccountMeta::new(escrow, false),
+                    AccountMeta::new(vault, false),
+                    AccountMeta::new_readonly(token_program, false),
+                    AccountMeta::new_readonly(system_program, false),
+                ],
+            ),
+        ),
+    ];
+    for (instruction, expected) in cases {
+        assert_eq!(instruction, expected);
+    }
+}
+
+#[test]
+fn refund_requires_maker_signature() {
+    // Add our built program binary
+    let mollusk: Mollusk = Mollusk::new(&crate::ID, "target/deploy/native_escrow_2024");
+
+    // Set our seed
+    let seed: u64 = 1337;
+
+    // Programs
+    let (token_program, token_program_account) = (spl_token::ID, program_account(&spl_token::ID));
+    let (system_program, system_program_account) = program::system_program();
+
+    // Accounts
+    let maker = Pubkey::new_from_array([0x01; 32]);
+    let mint_a = Pubkey::new_from_array([0x02; 32]);
+    let maker_ta_a = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &maker,
+        &mint_a,
+        &token_program,
+    );
+    let escrow = Pubkey::find_program_address(
+        &[b"escrow", maker.as_ref(), &seed.to_le_bytes()],
+        &crate::ID,
+    )
+    .0;
+    let vault = Pubkey::find_program_address(&[b"vault", escrow.as_ref()], &crate::ID).0;
+
+    // Create our instruction without the maker's signature
+    let mut instruction = refund_instruction(&maker, &mint_a, &maker_ta_a, &token_program, seed);
+    instruction.accounts[0].is_signer = false;
+
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &instruction,
+        &vec![
+            (maker, AccountSharedData::default()),
+            (mint_a, AccountSharedData::default()),
+            (maker_ta_a, AccountSharedData::default()),
+            (escrow, AccountSharedData::default()),
+            (vault, AccountSharedData::default()),
+            (token_program, token_program_account),
+            (system_program, system_program_account),
+        ],
+    );
+    assert!(matches!(
+        result.program_result,
+        ProgramResult::Failure(ProgramError::Custom(code)) if code == EscrowError::MissingSigner as u32
+    ));
+}
+
+#[test]
+fn take_partial() {
+    // Add our built program binary
+    let mut mollusk: Mollusk = Mollusk::new(&crate::ID, "target/deploy/native_escrow_2024");
+
+    // Set our seed
+    let seed: u64 = 1337;
+
+    // Programs
+    mollusk.add_program(&spl_token::ID, "src/tests/spl_token-3.5.0");
+    let (token_program, token_program_account) = (spl_token::ID, program_account(&spl_token::ID));
+    let (system_program, system_program_account) = program::system_program();
+
+    // Accounts
+    let taker = Pubkey::new_from_array([0x04; 32]);
+    let maker = Pubkey::new_from_array([0x01; 32]);
+    let mint_a = Pubkey::new_from_array([0x02; 32]);
+    let mint_b = Pubkey::new_from_array([0x03; 32]);
+    let taker_ta_a = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &taker,
+        &mint_a,
+        &token_program,
+    );
+    let taker_ta_b = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &taker,
+        &mint_b,
+        &token_program,
+    );
+    let maker_ta_b = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &maker,
+        &mint_b,
+        &token_program,
+    );
+    let (escrow, bump) = Pubkey::find_program_address(
+        &[b"escrow", maker.as_ref(), &seed.to_le_bytes()],
+        &crate::ID,
+    );
+    let (vault, vault_bump) =
+        Pubkey::find_program_address(&[b"vault", escrow.as_ref()], &crate::ID);
+
+    let treasury = Pubkey::new_from_array([0x08; 32]);
+
+    // Create our instruction filling a quarter of the vault
+    let instruction = take_partial_instruction(
+        &taker,
+        &maker,
+        &mint_a,
+        &mint_b,
+        &taker_ta_a,
+        &taker_ta_b,
+        &maker_ta_b,
+        &treasury,
+        &token_program,
+        seed,
+        25_000,
+    );
+
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &instruction,
+        &vec![
+            (
+                taker,
+                AccountSharedData::new(1_000_000_000, 0, &Pubkey::default()),
+            ),
+            (
+                maker,
+                AccountSharedData::new(1_000_000_000, 0, &Pubkey::default()),
+            ),
+            (mint_a, mint_account(&mollusk, Pubkey::new_from_array([0x05; 32]))),
+            (mint_b, mint_account(&mollusk, Pubkey::new_from_array([0x06; 32]))),
+            (taker_ta_a, token_account(&mollusk, mint_a, taker, 0)),
+            (taker_ta_b, token_account(&mollusk, mint_b, taker, 1_000_000_000)),
+            (maker_ta_b, token_account(&mollusk, mint_b, maker, 0)),
+            (
+                escrow,
+                escrow_account(
+                    &mollusk,
+                    Escrow {
+                        seed,
+                        maker,
+                        mint_a,
+                        mint_b,
+                        receive: 200_000,
+                        bump,
+                        vault_bump,
+                        ..Default::default()
+                    },
+                ),
+            ),
+            (vault, token_account(&mollusk, mint_a, escrow, 100_000)),
+            (token_program, token_program_account),
+            (system_program, system_program_account),
+            (CONFIG_ADDRESS, AccountSharedData::default()),
+            (treasury, AccountSharedData::default()),
         ],
     );
+    assert!(matches!(result.program_result, ProgramResult::Success));
+
+    // The escrow stays open with the remaining receive
+    let escrow_data = bytemuck::pod_read_unaligned::<Escrow>(resulting_account(&result, &escrow).data());
+    assert_eq!(escrow_data.receive, 150_000);
+    assert_eq!(
+        spl_token::state::Account::unpack(resulting_account(&result, &vault).data())
+            .unwrap()
+            .amount,
+        75_000
+    );
+    assert_eq!(
+        spl_token::state::Account::unpack(resulting_account(&result, &maker_ta_b).data())
+            .unwrap()
+            .amount,
+        50_000
+    );
+}
+
+#[test]
+fn expire() {
+    // Add our built program binary
+    let mut mollusk: Mollusk = Mollusk::new(&crate::ID, "target/deploy/native_escrow_2024");
+    mollusk.sysvars.clock.unix_timestamp = 1_000;
+
+    // Set our seed
+    let seed: u64 = 1337;
+
+    // Programs
+    mollusk.add_program(&spl_token::ID, "src/tests/spl_token-3.5.0");
+    let (token_program, token_program_account) = (spl_token::ID, program_account(&spl_token::ID));
+    let (system_program, system_program_account) = program::system_program();
+
+    // Accounts
+    let maker = Pubkey::new_from_array([0x01; 32]);
+    let mint_a = Pubkey::new_from_array([0x02; 32]);
+    let mint_b = Pubkey::new_from_array([0x03; 32]);
+    let maker_ta_a = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &maker,
+        &mint_a,
+        &token_program,
+    );
+    let (escrow, bump) = Pubkey::find_program_address(
+        &[b"escrow", maker.as_ref(), &seed.to_le_bytes()],
+        &crate::ID,
+    );
+    let (vault, vault_bump) =
+        Pubkey::find_program_address(&[b"vault", escrow.as_ref()], &crate::ID);
+
+    // Create our instruction without the maker's signature
+    let instruction = expire_instruction(&maker, &mint_a, &maker_ta_a, &token_program, seed);
+
+    let accounts = |expires_at: i64| {
+        vec![
+            (
+                maker,
+                AccountSharedData::new(1_000_000_000, 0, &Pubkey::default()),
+            ),
+            (mint_a, mint_account(&mollusk, Pubkey::new_from_array([0x05; 32]))),
+            (maker_ta_a, token_account(&mollusk, mint_a, maker, 1_000_000_000 - 100_000)),
+            (
+                escrow,
+                escrow_account(
+                    &mollusk,
+                    Escrow {
+                        seed,
+                        maker,
+                        mint_a,
+                        mint_b,
+                        receive: 100_000,
+                        expires_at,
+                        bump,
+                        vault_bump,
+                        ..Default::default()
+                    },
+                ),
+            ),
+            (vault, token_account(&mollusk, mint_a, escrow, 100_000)),
+            (token_program, token_program_account.clone()),
+            (system_program, system_program_account.clone()),
+        ]
+    };
+
+    // Escrows without expiry or not yet expired can't be closed
+    for expires_at in [0, 1_001] {
+        let result: mollusk_svm::result::InstructionResult =
+            mollusk.process_instruction(&instruction, &accounts(expires_at));
+        assert!(matches!(
+            result.program_result,
+            ProgramResult::Failure(ProgramError::Custom(code)) if code == EscrowError::EscrowNotExpired as u32
+        ));
+    }
+
+    let result: mollusk_svm::result::InstructionResult =
+        mollusk.process_instruction(&instruction, &accounts(1_000));
+    assert!(matches!(result.program_result, ProgramResult::Success));
+    assert_eq!(
+        spl_token::state::Account::unpack(resulting_account(&result, &maker_ta_a).data())
+            .unwrap()
+            .amount,
+        1_000_000_000
+    );
+}
+
+#[test]
+fn take_rejects_expired_escrow() {
+    // Add our built program binary
+    let mut mollusk: Mollusk = Mollusk::new(&crate::ID, "target/deploy/native_escrow_2024");
+    mollusk.sysvars.clock.unix_timestamp = 1_000;
+
+    // Set our seed
+    let seed: u64 = 1337;
+
+    // Programs
+    let (token_program, token_program_account) = (spl_token::ID, program_account(&spl_token::ID));
+    let (system_program, system_program_account) = program::system_program();
+
+    // Accounts
+    let taker = Pubkey::new_from_array([0x04; 32]);
+    let maker = Pubkey::new_from_array([0x01; 32]);
+    let mint_a = Pubkey::new_from_array([0x02; 32]);
+    let mint_b = Pubkey::new_from_array([0x03; 32]);
+    let treasury = Pubkey::new_from_array([0x08; 32]);
+    let (escrow, bump) = Pubkey::find_program_address(
+        &[b"escrow", maker.as_ref(), &seed.to_le_bytes()],
+        &crate::ID,
+    );
+
+    // Create our instruction
+    let instruction = take_instruction(
+        &taker,
+        &maker,
+        &mint_a,
+        &mint_b,
+        &Pubkey::new_from_array([0x05; 32]),
+        &Pubkey::new_from_array([0x06; 32]),
+        &Pubkey::new_from_array([0x07; 32]),
+        &treasury,
+        &token_program,
+        seed,
+    );
+    let mut accounts: Vec<(Pubkey, AccountSharedData)> = instruction
+        .accounts
+        .iter()
+        .map(|meta| (meta.pubkey, AccountSharedData::default()))
+        .collect();
+    accounts[9].1 = token_program_account;
+    accounts[10].1 = system_program_account;
+
+    // Escrows can't be taken from the second they expire
+    for expires_at in [1_000, 999] {
+        accounts[7].1 = escrow_account(
+            &mollusk,
+            Escrow {
+                seed,
+                maker,
+                mint_a,
+                mint_b,
+                receive: 100_000,
+                expires_at,
+                bump,
+                ..Default::default()
+            },
+        );
+        let result: mollusk_svm::result::InstructionResult =
+            mollusk.process_instruction(&instruction, &accounts);
+        assert!(matches!(
+            result.program_result,
+            ProgramResult::Failure(ProgramError::Custom(code)) if code == EscrowError::EscrowExpired as u32
+        ));
+    }
+}
+
+#[test]
+fn take_private_rejects_other_takers() {
+    // Add our built program binary
+    let mollusk: Mollusk = Mollusk::new(&crate::ID, "target/deploy/native_escrow_2024");
+
+    // Set our seed
+    let seed: u64 = 1337;
+
+    // Programs
+    let (token_program, token_program_account) = (spl_token::ID, program_account(&spl_token::ID));
+    let (system_program, system_program_account) = program::system_program();
+
+    // Accounts
+    let taker = Pubkey::new_from_array([0x04; 32]);
+    let maker = Pubkey::new_from_array([0x01; 32]);
+    let mint_a = Pubkey::new_from_array([0x02; 32]);
+    let mint_b = Pubkey::new_from_array([0x03; 32]);
+    let taker_ta_a = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &taker,
+        &mint_a,
+        &token_program,
+    );
+    let taker_ta_b = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &taker,
+        &mint_b,
+        &token_program,
+    );
+    let maker_ta_b = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &maker,
+        &mint_b,
+        &token_program,
+    );
+    let (escrow, bump) = Pubkey::find_program_address(
+        &[b"escrow", maker.as_ref(), &seed.to_le_bytes()],
+        &crate::ID,
+    );
+    let (vault, vault_bump) =
+        Pubkey::find_program_address(&[b"vault", escrow.as_ref()], &crate::ID);
+
+    let treasury = Pubkey::new_from_array([0x08; 32]);
+
+    // Create our instruction
+    let instruction = take_instruction(
+        &taker,
+        &maker,
+        &mint_a,
+        &mint_b,
+        &taker_ta_a,
+        &taker_ta_b,
+        &maker_ta_b,
+        &treasury,
+        &token_program,
+        seed,
+    );
+
+    // Escrow reserved for another counterparty
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &instruction,
+        &vec![
+            (taker, AccountSharedData::default()),
+            (maker, AccountSharedData::default()),
+            (mint_a, AccountSharedData::default()),
+            (mint_b, AccountSharedData::default()),
+            (taker_ta_a, AccountSharedData::default()),
+            (taker_ta_b, AccountSharedData::default()),
+            (maker_ta_b, AccountSharedData::default()),
+            (
+                escrow,
+                escrow_account(
+                    &mollusk,
+                    Escrow {
+                        seed,
+                        maker,
+                        mint_a,
+                        mint_b,
+                        receive: 100_000,
+                        taker: Pubkey::new_from_array([0x07; 32]),
+                        bump,
+                        vault_bump,
+                        ..Default::default()
+                    },
+                ),
+            ),
+            (vault, AccountSharedData::default()),
+            (token_program, token_program_account),
+            (system_program, system_program_account),
+            (CONFIG_ADDRESS, AccountSharedData::default()),
+            (treasury, AccountSharedData::default()),
+        ],
+    );
+    assert!(matches!(
+        result.program_result,
+        ProgramResult::Failure(ProgramError::Custom(code)) if code == EscrowError::InvalidTaker as u32
+    ));
+}
+
+#[test]
+fn make_native() {
+    // Add our built program binary
+    let mollusk: Mollusk = Mollusk::new(&crate::ID, "target/deploy/native_escrow_2024");
+
+    // Set our seed
+    let seed: u64 = 1337;
+
+    // Programs
+    let (token_program, token_program_account) = (spl_token::ID, program_account(&spl_token::ID));
+    let (system_program, system_program_account) = program::system_program();
+
+    // Accounts
+    let maker = Pubkey::new_from_array([0x01; 32]);
+    let mint_a = spl_token::native_mint::ID;
+    let mint_b = Pubkey::new_from_array([0x03; 32]);
+    let escrow = Pubkey::find_program_address(
+        &[b"escrow", maker.as_ref(), &seed.to_le_bytes()],
+        &crate::ID,
+    )
+    .0;
+    let vault = Pubkey::find_program_address(&[b"vault", escrow.as_ref()], &crate::ID).0;
+
+    // Create our instruction depositing SOL, with the maker's wallet in place of a token account
+    let instruction = make_instruction(
+        &maker,
+        &mint_a,
+        &mint_b,
+        &maker,
+        &token_program,
+        Make {
+            seed,
+            amount: 100_000,
+            receive: 100_000,
+            flags: FLAG_NATIVE_A,
+            ..Default::default()
+        },
+    );
+
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &instruction,
+        &vec![
+            (
+                maker,
+                AccountSharedData::new(1_000_000_000, 0, &Pubkey::default()),
+            ),
+            (mint_a, AccountSharedData::default()),
+            (mint_b, mint_account(&mollusk, Pubkey::new_from_array([0x06; 32]))),
+            (escrow, AccountSharedData::new(0, 0, &Pubkey::default())),
+            (vault, AccountSharedData::new(0, 0, &Pubkey::default())),
+            (token_program, token_program_account),
+            (system_program, system_program_account),
+            (CONFIG_ADDRESS, AccountSharedData::default()),
+            (find_mint_entry_address(&mint_a).0, AccountSharedData::default()),
+            (find_mint_entry_address(&mint_b).0, AccountSharedData::default()),
+        ],
+    );
+    assert!(matches!(result.program_result, ProgramResult::Success));
+
+    // The SOL is held in the escrow on top of its rent, and no vault is created
+    assert_eq!(
+        resulting_account(&result, &escrow).lamports(),
+        mollusk
+            .sysvars
+            .rent
+            .minimum_balance(mem::size_of::<Escrow>())
+            + 100_000
+    );
+    assert_eq!(resulting_account(&result, &vault).lamports(), 0);
+}
+
+#[test]
+fn make_rejects_invalid_approvers() {
+    // Add our built program binary
+    let mollusk: Mollusk = Mollusk::new(&crate::ID, "target/deploy/native_escrow_2024");
+
+    // Set our seed
+    let seed: u64 = 1337;
+
+    // Programs
+    let (token_program, token_program_account) = (spl_token::ID, program_account(&spl_token::ID));
+    let (system_program, system_program_account) = program::system_program();
+
+    // Accounts
+    let maker = Pubkey::new_from_array([0x01; 32]);
+    let mint_a = spl_token::native_mint::ID;
+    let mint_b = Pubkey::new_from_array([0x03; 32]);
+    let approver = Pubkey::new_from_array([0x0a; 32]);
+    let escrow = Pubkey::find_program_address(
+        &[b"escrow", maker.as_ref(), &seed.to_le_bytes()],
+        &crate::ID,
+    )
+    .0;
+    let vault = Pubkey::find_program_address(&[b"vault", escrow.as_ref()], &crate::ID).0;
+
+    // A repeated approver, or one listed after a default key, is rejected
+    for approvers in [
+        [approver, approver, Pubkey::default(), Pubkey::default(), Pubkey::default()],
+        [approver, Pubkey::default(), approver, Pubkey::default(), Pubkey::default()],
+    ] {
+        let instruction = make_instruction(
+            &maker,
+            &mint_a,
+            &mint_b,
+            &maker,
+            &token_program,
+            Make {
+                seed,
+                amount: 100_000,
+                receive: 100_000,
+                flags: FLAG_NATIVE_A,
+                approvers,
+                threshold: 2,
+                ..Default::default()
+            },
+        );
+
+        let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+            &instruction,
+            &vec![
+                (
+                    maker,
+                    AccountSharedData::new(1_000_000_000, 0, &Pubkey::default()),
+                ),
+                (mint_a, AccountSharedData::default()),
+                (mint_b, mint_account(&mollusk, Pubkey::new_from_array([0x06; 32]))),
+                (escrow, AccountSharedData::new(0, 0, &Pubkey::default())),
+                (vault, AccountSharedData::new(0, 0, &Pubkey::default())),
+                (token_program, token_program_account.clone()),
+                (system_program, system_program_account.clone()),
+                (CONFIG_ADDRESS, AccountSharedData::default()),
+                (find_mint_entry_address(&mint_a).0, AccountSharedData::default()),
+                (find_mint_entry_address(&mint_b).0, AccountSharedData::default()),
+            ],
+        );
+        assert!(matches!(
+            result.program_result,
+            ProgramResult::Failure(ProgramError::Custom(code)) if code == EscrowError::InvalidApprover as u32
+        ));
+    }
+}
+
+#[test]
+fn refund_rejects_other_account_kinds() {
+    // Add our built program binary
+    let mollusk: Mollusk = Mollusk::new(&crate::ID, "target/deploy/native_escrow_2024");
+
+    // Set our seed
+    let seed: u64 = 1337;
+
+    // Programs
+    let (token_program, token_program_account) = (spl_token::ID, program_account(&spl_token::ID));
+    let (system_program, system_program_account) = program::system_program();
+
+    // Accounts
+    let maker = Pubkey::new_from_array([0x01; 32]);
+    let mint_a = Pubkey::new_from_array([0x02; 32]);
+    let mint_b = Pubkey::new_from_array([0x03; 32]);
+    let maker_ta_a = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &maker,
+        &mint_a,
+        &token_program,
+    );
+    let (escrow, bump) = Pubkey::find_program_address(
+        &[b"escrow", maker.as_ref(), &seed.to_le_bytes()],
+        &crate::ID,
+    );
+    let vault = Pubkey::find_program_address(&[b"vault", escrow.as_ref()], &crate::ID).0;
+
+    // Create our instruction
+    let instruction = refund_instruction(&maker, &mint_a, &maker_ta_a, &token_program, seed);
+
+    // Program-owned account of the right size but another kind
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &instruction,
+        &vec![
+            (maker, AccountSharedData::default()),
+            (mint_a, AccountSharedData::default()),
+            (maker_ta_a, AccountSharedData::default()),
+            (
+                escrow,
+                escrow_account(
+                    &mollusk,
+                    Escrow {
+                        discriminator: 0,
+                        seed,
+                        maker,
+                        mint_a,
+                        mint_b,
+                        receive: 100_000,
+                        bump,
+                        ..Default::default()
+                    },
+                ),
+            ),
+            (vault, AccountSharedData::default()),
+            (token_program, token_program_account),
+            (system_program, system_program_account),
+        ],
+    );
+    assert!(matches!(
+        result.program_result,
+        ProgramResult::Failure(ProgramError::Custom(code)) if code == EscrowError::InvalidDiscriminator as u32
+    ));
+}
+
+#[test]
+fn migrate() {
+    // Add our built program binary
+    let mollusk: Mollusk = Mollusk::new(&crate::ID, "target/deploy/native_escrow_2024");
+
+    // Set our seed
+    let seed: u64 = 1337;
+
+    // Programs
+    let (system_program, system_program_account) = program::system_program();
+
+    // Accounts
+    let payer = Pubkey::new_from_array([0x08; 32]);
+    let maker = Pubkey::new_from_array([0x01; 32]);
+    let mint_a = Pubkey::new_from_array([0x02; 32]);
+    let mint_b = Pubkey::new_from_array([0x03; 32]);
+    let (escrow, bump) = Pubkey::find_program_address(
+        &[b"escrow", maker.as_ref(), &seed.to_le_bytes()],
+        &crate::ID,
+    );
+    let vault_bump = Pubkey::find_program_address(&[b"vault", escrow.as_ref()], &crate::ID).1;
+
+    // Escrow in the original layout
+    let mut escrow_account = AccountSharedData::new(
+        mollusk
+            .sysvars
+            .rent
+            .minimum_balance(mem::size_of::<EscrowV1>()),
+        mem::size_of::<EscrowV1>(),
+        &crate::ID,
+    );
+    escrow_account.set_data_from_slice(bytes_of::<EscrowV1>(&EscrowV1 {
+        seed,
+        maker,
+        mint_a,
+        mint_b,
+        receive: 100_000,
+    }));
+
+    // Create our instruction
+    let instruction = migrate_instruction(&payer, &maker, seed);
+
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &instruction,
+        &vec![
+            (
+                payer,
+                AccountSharedData::new(1_000_000_000, 0, &Pubkey::default()),
+            ),
+            (maker, AccountSharedData::default()),
+            (escrow, escrow_account),
+            (system_program, system_program_account),
+        ],
+    );
+    assert!(matches!(result.program_result, ProgramResult::Success));
+
+    // The escrow is rewritten in the current layout, rent exempt and with its terms intact
+    let escrow_account = resulting_account(&result, &escrow);
+    assert_eq!(
+        escrow_account.lamports(),
+        mollusk
+            .sysvars
+            .rent
+            .minimum_balance(mem::size_of::<Escrow>())
+    );
+    let escrow_data = bytemuck::pod_read_unaligned::<Escrow>(escrow_account.data());
+    assert_eq!(escrow_data.version, ESCROW_VERSION);
+    assert_eq!(escrow_data.bump, bump);
+    assert_eq!(escrow_data.vault_bump, vault_bump);
+    assert_eq!(escrow_data.seed, seed);
+    assert_eq!(escrow_data.maker, maker);
+    assert_eq!(escrow_data.mint_a, mint_a);
+    assert_eq!(escrow_data.mint_b, mint_b);
+    assert_eq!(escrow_data.receive, 100_000);
+}
+
+#[test]
+fn migrate_keeps_excess_lamports() {
+    // Add our built program binary
+    let mollusk: Mollusk = Mollusk::new(&crate::ID, "target/deploy/native_escrow_2024");
+
+    // Set our seed
+    let seed: u64 = 1337;
+
+    // Programs
+    let (system_program, system_program_account) = program::system_program();
+
+    // Accounts
+    let payer = Pubkey::new_from_array([0x08; 32]);
+    let maker = Pubkey::new_from_array([0x01; 32]);
+    let escrow = Pubkey::find_program_address(
+        &[b"escrow", maker.as_ref(), &seed.to_le_bytes()],
+        &crate::ID,
+    )
+    .0;
+
+    // Escrow in the original layout, holding SOL on top of its rent
+    let mut escrow_account = AccountSharedData::new(
+        mollusk
+            .sysvars
+            .rent
+            .minimum_balance(mem::size_of::<EscrowV1>())
+            + 100_000,
+        mem::size_of::<EscrowV1>(),
+        &crate::ID,
+    );
+    escrow_account.set_data_from_slice(bytes_of::<EscrowV1>(&EscrowV1 {
+        seed,
+        maker,
+        mint_a: Pubkey::new_from_array([0x02; 32]),
+        mint_b: Pubkey::new_from_array([0x03; 32]),
+        receive: 100_000,
+    }));
+
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &migrate_instruction(&payer, &maker, seed),
+        &vec![
+            (
+                payer,
+                AccountSharedData::new(1_000_000_000, 0, &Pubkey::default()),
+            ),
+            (maker, AccountSharedData::default()),
+            (escrow, escrow_account),
+            (system_program, system_program_account),
+        ],
+    );
+    assert!(matches!(result.program_result, ProgramResult::Success));
+
+    // The payer covers the extra rent, and the SOL above rent is untouched
+    assert_eq!(
+        resulting_account(&result, &escrow).lamports(),
+        mollusk
+            .sysvars
+            .rent
+            .minimum_balance(mem::size_of::<Escrow>())
+            + 100_000
+    );
+}
+
+#[test]
+fn migrate_rejects_unsupported_version() {
+    // Add our built program binary
+    let mollusk: Mollusk = Mollusk::new(&crate::ID, "target/deploy/native_escrow_2024");
+
+    // Set our seed
+    let seed: u64 = 1337;
+
+    // Programs
+    let (system_program, system_program_account) = program::system_program();
+
+    // Accounts
+    let payer = Pubkey::new_from_array([0x08; 32]);
+    let maker = Pubkey::new_from_array([0x01; 32]);
+    let (escrow, bump) = Pubkey::find_program_address(
+        &[b"escrow", maker.as_ref(), &seed.to_le_bytes()],
+        &crate::ID,
+    );
+
+    // Only the original layout and the current one exist
+    let escrow_account = escrow_account(
+        &mollusk,
+        Escrow {
+            version: ESCROW_VERSION + 1,
+            seed,
+            maker,
+            bump,
+            ..Default::default()
+        },
+    );
+
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &migrate_instruction(&payer, &maker, seed),
+        &vec![
+            (
+                payer,
+                AccountSharedData::new(1_000_000_000, 0, &Pubkey::default()),
+            ),
+            (maker, AccountSharedData::default()),
+            (escrow, escrow_account),
+            (system_program, system_program_account),
+        ],
+    );
+    assert!(matches!(
+        result.program_result,
+        ProgramResult::Failure(ProgramError::Custom(code)) if code == EscrowError::UnsupportedVersion as u32
+    ));
+}
+
+#[test]
+fn update() {
+    // Add our built program binary
+    let mut mollusk: Mollusk = Mollusk::new(&crate::ID, "target/deploy/native_escrow_2024");
+    mollusk.sysvars.clock.unix_timestamp = 1_000;
+
+    // Set our seed
+    let seed: u64 = 1337;
+
+    // Accounts
+    let maker = Pubkey::new_from_array([0x01; 32]);
+    let mint_a = Pubkey::new_from_array([0x02; 32]);
+    let mint_b = Pubkey::new_from_array([0x03; 32]);
+    let new_mint_b = Pubkey::new_from_array([0x09; 32]);
+    let (escrow, bump) = Pubkey::find_program_address(
+        &[b"escrow", maker.as_ref(), &seed.to_le_bytes()],
+        &crate::ID,
+    );
+    let accounts = |expires_at: i64| {
+        vec![
+            (maker, AccountSharedData::default()),
+            (new_mint_b, mint_account(&mollusk, Pubkey::new_from_array([0x06; 32]))),
+            (
+                escrow,
+                escrow_account(
+                    &mollusk,
+                    Escrow {
+                        seed,
+                        maker,
+                        mint_a,
+                        mint_b,
+                        receive: 100_000,
+                        expires_at,
+                        bump,
+                        ..Default::default()
+                    },
+                ),
+            ),
+            (CONFIG_ADDRESS, AccountSharedData::default()),
+            (find_mint_entry_address(&new_mint_b).0, AccountSharedData::default()),
+        ]
+    };
+
+    // Reprice the escrow and push its expiry back
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &update_instruction(
+            &maker,
+            &new_mint_b,
+            seed,
+            Update {
+                receive: 250_000,
+                expires_at: 3_000,
+                flags: UPDATE_FLAG_SET_EXPIRY,
+                ..Default::default()
+            },
+        ),
+        &accounts(2_000),
+    );
+    assert!(matches!(result.program_result, ProgramResult::Success));
+
+    // The escrow is repriced in place with its new expiry
+    let escrow_data =
+        bytemuck::pod_read_unaligned::<Escrow>(resulting_account(&result, &escrow).data());
+    assert_eq!(escrow_data.receive, 250_000);
+    assert_eq!(escrow_data.expires_at, 3_000);
+    assert_eq!(escrow_data.mint_b, new_mint_b);
+    assert_eq!(escrow_data.mint_a, mint_a);
+
+    // Without the flag, a zero expires_at leaves the expiry untouched
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &update_instruction(
+            &maker,
+            &new_mint_b,
+            seed,
+            Update {
+                receive: 250_000,
+                ..Default::default()
+            },
+        ),
+        &accounts(2_000),
+    );
+    assert!(matches!(result.program_result, ProgramResult::Success));
+    let escrow_data =
+        bytemuck::pod_read_unaligned::<Escrow>(resulting_account(&result, &escrow).data());
+    assert_eq!(escrow_data.receive, 250_000);
+    assert_eq!(escrow_data.expires_at, 2_000);
+
+    // With the flag, a zero expires_at removes the expiry
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &update_instruction(
+            &maker,
+            &new_mint_b,
+            seed,
+            Update {
+                receive: 250_000,
+                flags: UPDATE_FLAG_SET_EXPIRY,
+                ..Default::default()
+            },
+        ),
+        &accounts(2_000),
+    );
+    assert!(matches!(result.program_result, ProgramResult::Success));
+    let escrow_data =
+        bytemuck::pod_read_unaligned::<Escrow>(resulting_account(&result, &escrow).data());
+    assert_eq!(escrow_data.expires_at, 0);
+
+    // An expired escrow can't be brought back to life
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &update_instruction(
+            &maker,
+            &new_mint_b,
+            seed,
+            Update {
+                receive: 250_000,
+                expires_at: 3_000,
+                flags: UPDATE_FLAG_SET_EXPIRY,
+                ..Default::default()
+            },
+        ),
+        &accounts(1_000),
+    );
+    assert!(matches!(
+        result.program_result,
+        ProgramResult::Failure(ProgramError::Custom(code)) if code == EscrowError::EscrowExpired as u32
+    ));
+
+    // Unknown flags are rejected
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &update_instruction(
+            &maker,
+            &new_mint_b,
+            seed,
+            Update {
+                receive: 250_000,
+                flags: 1 << 1,
+                ..Default::default()
+            },
+        ),
+        &accounts(2_000),
+    );
+    assert!(matches!(
+        result.program_result,
+        ProgramResult::Failure(ProgramError::Custom(code)) if code == EscrowError::InvalidFlags as u32
+    ));
+}
+
+#[test]
+fn update_rejects_denied_mint() {
+    // Add our built program binary
+    let mollusk: Mollusk = Mollusk::new(&crate::ID, "target/deploy/native_escrow_2024");
+
+    // Set our seed
+    let seed: u64 = 1337;
+
+    // Accounts
+    let maker = Pubkey::new_from_array([0x01; 32]);
+    let mint_a = Pubkey::new_from_array([0x02; 32]);
+    let mint_b = Pubkey::new_from_array([0x03; 32]);
+    let new_mint_b = Pubkey::new_from_array([0x09; 32]);
+    let (new_mint_b_entry, new_mint_b_entry_bump) = find_mint_entry_address(&new_mint_b);
+    let (escrow, bump) = Pubkey::find_program_address(
+        &[b"escrow", maker.as_ref(), &seed.to_le_bytes()],
+        &crate::ID,
+    );
+
+    // The new mint B is denied
+    let mut new_mint_b_entry_account = AccountSharedData::new(
+        mollusk
+            .sysvars
+            .rent
+            .minimum_balance(mem::size_of::<MintEntry>()),
+        mem::size_of::<MintEntry>(),
+        &crate::ID,
+    );
+    new_mint_b_entry_account.set_data_from_slice(bytes_of::<MintEntry>(&MintEntry {
+        status: MINT_STATUS_DENIED,
+        bump: new_mint_b_entry_bump,
+        mint: new_mint_b,
+        ..Default::default()
+    }));
+
+    // Repricing into the denied mint is rejected
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &update_instruction(
+            &maker,
+            &new_mint_b,
+            seed,
+            Update {
+                receive: 250_000,
+                ..Default::default()
+            },
+        ),
+        &vec![
+            (maker, AccountSharedData::default()),
+            (new_mint_b, AccountSharedData::default()),
+            (
+                escrow,
+                escrow_account(
+                    &mollusk,
+                    Escrow {
+                        seed,
+                        maker,
+                        mint_a,
+                        mint_b,
+                        receive: 100_000,
+                        bump,
+                        ..Default::default()
+                    },
+                ),
+            ),
+            (
+                CONFIG_ADDRESS,
+                config_account(
+                    &mollusk,
+                    Config {
+                        flags: CONFIG_FLAG_DENYLIST,
+                        ..Default::default()
+                    },
+                ),
+            ),
+            (new_mint_b_entry, new_mint_b_entry_account),
+        ],
+    );
+    assert!(matches!(
+        result.program_result,
+        ProgramResult::Failure(ProgramError::Custom(code)) if code == EscrowError::MintNotAllowed as u32
+    ));
+}
+
+#[test]
+fn deposit_scales_receive() {
+    // Add our built program binary
+    let mut mollusk: Mollusk = Mollusk::new(&crate::ID, "target/deploy/native_escrow_2024");
+
+    // Set our seed
+    let seed: u64 = 1337;
+
+    // Programs
+    mollusk.add_program(&spl_token::ID, "src/tests/spl_token-3.5.0");
+    let (token_program, token_program_account) = (spl_token::ID, program_account(&spl_token::ID));
+    let (system_program, system_program_account) = program::system_program();
+
+    // Accounts
+    let maker = Pubkey::new_from_array([0x01; 32]);
+    let mint_a = Pubkey::new_from_array([0x02; 32]);
+    let mint_b = Pubkey::new_from_array([0x03; 32]);
+    let maker_ta_a = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &maker,
+        &mint_a,
+        &token_program,
+    );
+    let (escrow, bump) = Pubkey::find_program_address(
+        &[b"escrow", maker.as_ref(), &seed.to_le_bytes()],
+        &crate::ID,
+    );
+    let (vault, vault_bump) =
+        Pubkey::find_program_address(&[b"vault", escrow.as_ref()], &crate::ID);
+
+    // Create our instruction topping up the vault by half
+    let instruction = deposit_instruction(&maker, &mint_a, &maker_ta_a, &token_program, seed, 50_000);
+
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &instruction,
+        &vec![
+            (
+                maker,
+                AccountSharedData::new(1_000_000_000, 0, &Pubkey::default()),
+            ),
+            (mint_a, mint_account(&mollusk, Pubkey::new_from_array([0x05; 32]))),
+            (maker_ta_a, token_account(&mollusk, mint_a, maker, 1_000_000)),
+            (
+                escrow,
+                escrow_account(
+                    &mollusk,
+                    Escrow {
+                        seed,
+                        maker,
+                        mint_a,
+                        mint_b,
+                        receive: 200_000,
+                        flags: FLAG_SCALE_RECEIVE,
+                        approvals: 0b001,
+                        bump,
+                        vault_bump,
+                        ..Default::default()
+                    },
+                ),
+            ),
+            (vault, token_account(&mollusk, mint_a, escrow, 100_000)),
+            (token_program, token_program_account),
+            (system_program, system_program_account),
+        ],
+    );
+    assert!(matches!(result.program_result, ProgramResult::Success));
+
+    // Receive scales with the vault
+    let escrow_data = bytemuck::pod_read_unaligned::<Escrow>(resulting_account(&result, &escrow).data());
+    assert_eq!(escrow_data.receive, 300_000);
+
+    // Approvals given for the old vault amount are cleared
+    assert_eq!(escrow_data.approvals, 0);
+    assert_eq!(
+        spl_token::state::Account::unpack(resulting_account(&result, &vault).data())
+            .unwrap()
+            .amount,
+        150_000
+    );
+    assert_eq!(
+        spl_token::state::Account::unpack(resulting_account(&result, &maker_ta_a).data())
+            .unwrap()
+            .amount,
+        950_000
+    );
+}
+
+#[test]
+fn withdraw() {
+    // Add our built program binary
+    let mut mollusk: Mollusk = Mollusk::new(&crate::ID, "target/deploy/native_escrow_2024");
+
+    // Set our seed
+    let seed: u64 = 1337;
+
+    // Programs
+    mollusk.add_program(&spl_token::ID, "src/tests/spl_token-3.5.0");
+    let (token_program, token_program_account) = (spl_token::ID, program_account(&spl_token::ID));
+    let (system_program, system_program_account) = program::system_program();
+
+    // Accounts
+    let maker = Pubkey::new_from_array([0x01; 32]);
+    let mint_a = Pubkey::new_from_array([0x02; 32]);
+    let mint_b = Pubkey::new_from_array([0x03; 32]);
+    let maker_ta_a = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &maker,
+        &mint_a,
+        &token_program,
+    );
+    let (escrow, bump) = Pubkey::find_program_address(
+        &[b"escrow", maker.as_ref(), &seed.to_le_bytes()],
+        &crate::ID,
+    );
+    let (vault, vault_bump) =
+        Pubkey::find_program_address(&[b"vault", escrow.as_ref()], &crate::ID);
+
+    // Create our instruction taking back a quarter of the vault
+    let instruction = withdraw_instruction(&maker, &mint_a, &maker_ta_a, &token_program, seed, 25_000);
+
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &instruction,
+        &vec![
+            (
+                maker,
+                AccountSharedData::new(1_000_000_000, 0, &Pubkey::default()),
+            ),
+            (mint_a, mint_account(&mollusk, Pubkey::new_from_array([0x05; 32]))),
+            (maker_ta_a, token_account(&mollusk, mint_a, maker, 0)),
+            (
+                escrow,
+                escrow_account(
+                    &mollusk,
+                    Escrow {
+                        seed,
+                        maker,
+                        mint_a,
+                        mint_b,
+                        receive: 200_000,
+                        flags: 0,
+                        approvals: 0b001,
+                        bump,
+                        vault_bump,
+                        ..Default::default()
+                    },
+                ),
+            ),
+            (vault, token_account(&mollusk, mint_a, escrow, 100_000)),
+            (token_program, token_program_account),
+            (system_program, system_program_account),
+        ],
+    );
+    assert!(matches!(result.program_result, ProgramResult::Success));
+
+    // Receive stays fixed without proportional pricing
+    let escrow_data = bytemuck::pod_read_unaligned::<Escrow>(resulting_account(&result, &escrow).data());
+    assert_eq!(escrow_data.receive, 200_000);
+
+    // Approvals given for the old vault amount are cleared
+    assert_eq!(escrow_data.approvals, 0);
+    assert_eq!(
+        spl_token::state::Account::unpack(resulting_account(&result, &vault).data())
+            .unwrap()
+            .amount,
+        75_000
+    );
+    assert_eq!(
+        spl_token::state::Account::unpack(resulting_account(&result, &maker_ta_a).data())
+            .unwrap()
+            .amount,
+        25_000
+    );
+}
+
+#[test]
+fn take_with_fee() {
+    // Add our built program binary
+    let mut mollusk: Mollusk = Mollusk::new(&crate::ID, "target/deploy/native_escrow_2024");
+
+    // Set our seed
+    let seed: u64 = 1337;
+
+    // Programs
+    mollusk.add_program(&spl_token::ID, "src/tests/spl_token-3.5.0");
+    let (token_program, token_program_account) = (spl_token::ID, program_account(&spl_token::ID));
+    let (system_program, system_program_account) = program::system_program();
+
+    // The precomputed config PDA matches its seeds
+    assert_eq!(
+        Pubkey::find_program_address(&[b"config"], &crate::ID),
+        (CONFIG_ADDRESS, CONFIG_BUMP)
+    );
+
+    // Accounts
+    let taker = Pubkey::new_from_array([0x04; 32]);
+    let maker = Pubkey::new_from_array([0x01; 32]);
+    let mint_a = Pubkey::new_from_array([0x02; 32]);
+    let mint_b = Pubkey::new_from_array([0x03; 32]);
+    let treasury_owner = Pubkey::new_from_array([0x08; 32]);
+    let taker_ta_a = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &taker,
+        &mint_a,
+        &token_program,
+    );
+    let taker_ta_b = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &taker,
+        &mint_b,
+        &token_program,
+    );
+    let maker_ta_b = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &maker,
+        &mint_b,
+        &token_program,
+    );
+    let treasury = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &treasury_owner,
+        &mint_b,
+        &token_program,
+    );
+    let (escrow, bump) = Pubkey::find_program_address(
+        &[b"escrow", maker.as_ref(), &seed.to_le_bytes()],
+        &crate::ID,
+    );
+    let (vault, vault_bump) =
+        Pubkey::find_program_address(&[b"vault", escrow.as_ref()], &crate::ID);
+
+    // Config charging 1% of the mint B payment
+    let config_account = config_account(
+        &mollusk,
+        Config {
+            fee_bps: 100,
+            treasury: treasury_owner,
+            ..Default::default()
+        },
+    );
+
+    // Create our instruction
+    let instruction = take_instruction(
+        &taker,
+        &maker,
+        &mint_a,
+        &mint_b,
+        &taker_ta_a,
+        &taker_ta_b,
+        &maker_ta_b,
+        &treasury,
+        &token_program,
+        seed,
+    );
+
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &instruction,
+        &vec![
+            (
+                taker,
+                AccountSharedData::new(1_000_000_000, 0, &Pubkey::default()),
+            ),
+            (
+                maker,
+                AccountSharedData::new(1_000_000_000, 0, &Pubkey::default()),
+            ),
+            (mint_a, mint_account(&mollusk, Pubkey::new_from_array([0x05; 32]))),
+            (mint_b, mint_account(&mollusk, Pubkey::new_from_array([0x06; 32]))),
+            (taker_ta_a, token_account(&mollusk, mint_a, taker, 0)),
+            (taker_ta_b, token_account(&mollusk, mint_b, taker, 1_000_000_000)),
+            (maker_ta_b, token_account(&mollusk, mint_b, maker, 0)),
+            (
+                escrow,
+                escrow_account(
+                    &mollusk,
+                    Escrow {
+                        seed,
+                        maker,
+                        mint_a,
+                        mint_b,
+                        receive: 100_000,
+                        bump,
+                        vault_bump,
+                        ..Default::default()
+                    },
+                ),
+            ),
+            (vault, token_account(&mollusk, mint_a, escrow, 100_000)),
+            (token_program, token_program_account),
+            (system_program, system_program_account),
+            (CONFIG_ADDRESS, config_account),
+            (treasury, token_account(&mollusk, mint_b, treasury_owner, 0)),
+        ],
+    );
+    assert!(matches!(result.program_result, ProgramResult::Success));
+
+    // The fee is skimmed from the maker's payment into the treasury
+    assert_eq!(
+        spl_token::state::Account::unpack(resulting_account(&result, &maker_ta_b).data())
+            .unwrap()
+            .amount,
+        99_000
+    );
+    assert_eq!(
+        spl_token::state::Account::unpack(resulting_account(&result, &treasury).data())
+            .unwrap()
+            .amount,
+        1_000
+    );
+}
+
+#[test]
+fn initialize_config_requires_upgrade_authority() {
+    // Add our built program binary
+    let mollusk: Mollusk = Mollusk::new(&crate::ID, "target/deploy/native_escrow_2024");
+    let (system_program, system_program_account) = program::system_program();
+
+    // Accounts
+    let authority = Pubkey::new_from_array([0x01; 32]);
+    let instruction = initialize_config_instruction(
+        &authority,
+        InitializeConfig {
+            fee_bps: 100,
+            treasury: Pubkey::new_from_array([0x08; 32]),
+            ..Default::default()
+        },
+    );
+    let program_data = instruction.accounts[2].pubkey;
+
+    // Program data without an upgrade authority
+    let mut program_data_account = AccountSharedData::new(
+        1_000_000_000,
+        45,
+        &solana_sdk::bpf_loader_upgradeable::ID,
+    );
+    program_data_account.data_as_mut_slice()[0] = 3;
+
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &instruction,
+        &vec![
+            (
+                authority,
+                AccountSharedData::new(1_000_000_000, 0, &Pubkey::default()),
+            ),
+            (CONFIG_ADDRESS, AccountSharedData::default()),
+            (program_data, program_data_account),
+            (system_program, system_program_account),
+        ],
+    );
+    assert!(matches!(
+        result.program_result,
+        ProgramResult::Failure(ProgramError::Custom(code)) if code == EscrowError::InvalidAuthority as u32
+    ));
+}
+
+#[test]
+fn make_rejects_when_paused() {
+    // Add our built program binary
+    let mollusk: Mollusk = Mollusk::new(&crate::ID, "target/deploy/native_escrow_2024");
+
+    // Programs
+    let (token_program, token_program_account) = (spl_token::ID, program_account(&spl_token::ID));
+    let (system_program, system_program_account) = program::system_program();
+
+    // Accounts
+    let maker = Pubkey::new_from_array([0x01; 32]);
+    let mint_a = Pubkey::new_from_array([0x02; 32]);
+    let mint_b = Pubkey::new_from_array([0x03; 32]);
+    let maker_ta_a = Pubkey::new_from_array([0x04; 32]);
+
+    // Create our instruction
+    let instruction = make_instruction(
+        &maker,
+        &mint_a,
+        &mint_b,
+        &maker_ta_a,
+        &token_program,
+        Make {
+            seed: 1337,
+            amount: 100_000,
+            receive: 100_000,
+            ..Default::default()
+        },
+    );
+    let escrow = instruction.accounts[4].pubkey;
+    let vault = instruction.accounts[5].pubkey;
+
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &instruction,
+        &vec![
+            (maker, AccountSharedData::default()),
+            (mint_a, AccountSharedData::default()),
+            (mint_b, AccountSharedData::default()),
+            (maker_ta_a, AccountSharedData::default()),
+            (escrow, AccountSharedData::default()),
+            (vault, AccountSharedData::default()),
+            (token_program, token_program_account),
+            (system_program, system_program_account),
+            (
+                CONFIG_ADDRESS,
+                config_account(
+                    &mollusk,
+                    Config {
+                        flags: CONFIG_FLAG_PAUSED,
+                        ..Default::default()
+                    },
+                ),
+            ),
+            (find_mint_entry_address(&mint_a).0, AccountSharedData::default()),
+            (find_mint_entry_address(&mint_b).0, AccountSharedData::default()),
+        ],
+    );
+    assert!(matches!(
+        result.program_result,
+        ProgramResult::Failure(ProgramError::Custom(code)) if code == EscrowError::ProtocolPaused as u32
+    ));
+}
+
+#[test]
+fn transfer_admin() {
+    // Add our built program binary
+    let mollusk: Mollusk = Mollusk::new(&crate::ID, "target/deploy/native_escrow_2024");
+
+    // Accounts
+    let admin = Pubkey::new_from_array([0x01; 32]);
+    let new_admin = Pubkey::new_from_array([0x02; 32]);
+    let treasury = Pubkey::new_from_array([0x08; 32]);
+
+    // Admin pauses trading and nominates a new admin
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &update_config_instruction(
+            &admin,
+            UpdateConfig {
+                fee_bps: 50,
+                flags: CONFIG_FLAG_PAUSED,
+                treasury,
+                pending_admin: new_admin,
+                ..Default::default()
+            },
+        ),
+        &vec![
+            (admin, AccountSharedData::default()),
+            (
+                CONFIG_ADDRESS,
+                config_account(
+                    &mollusk,
+                    Config {
+                        treasury,
+                        admin,
+                        ..Default::default()
+                    },
+                ),
+            ),
+        ],
+    );
+    assert!(matches!(result.program_result, ProgramResult::Success));
+    let config = resulting_account(&result, &CONFIG_ADDRESS).clone();
+
+    // The old admin stays in charge until the nomination is accepted
+    let config_data = bytemuck::pod_read_unaligned::<Config>(config.data());
+    assert_eq!(config_data.admin, admin);
+    assert_eq!(config_data.fee_bps, 50);
+    assert!(config_data.is_paused());
+
+    // Nobody else can accept the nomination
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &accept_admin_instruction(&treasury),
+        &vec![
+            (treasury, AccountSharedData::default()),
+            (CONFIG_ADDRESS, config.clone()),
+        ],
+    );
+    assert!(matches!(
+        result.program_result,
+        ProgramResult::Failure(ProgramError::Custom(code)) if code == EscrowError::InvalidAuthority as u32
+    ));
+
+    // The nominated admin accepts
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &accept_admin_instruction(&new_admin),
+        &vec![
+            (new_admin, AccountSharedData::default()),
+            (CONFIG_ADDRESS, config),
+        ],
+    );
+    assert!(matches!(result.program_result, ProgramResult::Success));
+    let config_data =
+        bytemuck::pod_read_unaligned::<Config>(resulting_account(&result, &CONFIG_ADDRESS).data());
+    assert_eq!(config_data.admin, new_admin);
+    assert_eq!(config_data.pending_admin, Pubkey::default());
+}
+
+#[test]
+fn make_rejects_unlisted_mint() {
+    // Add our built program binary
+    let mollusk: Mollusk = Mollusk::new(&crate::ID, "target/deploy/native_escrow_2024");
+
+    // Programs
+    let (token_program, token_program_account) = (spl_token::ID, program_account(&spl_token::ID));
+    let (system_program, system_program_account) = program::system_program();
+
+    // Accounts
+    let maker = Pubkey::new_from_array([0x01; 32]);
+    let mint_a = Pubkey::new_from_array([0x02; 32]);
+    let mint_b = Pubkey::new_from_array([0x03; 32]);
+    let maker_ta_a = Pubkey::new_from_array([0x04; 32]);
+    let (mint_a_entry, mint_a_entry_bump) = find_mint_entry_address(&mint_a);
+
+    // Create our instruction
+    let instruction = make_instruction(
+        &maker,
+        &mint_a,
+        &mint_b,
+        &maker_ta_a,
+        &token_program,
+        Make {
+            seed: 1337,
+            amount: 100_000,
+            receive: 100_000,
+            ..Default::default()
+        },
+    );
+    let escrow = instruction.accounts[4].pubkey;
+    let vault = instruction.accounts[5].pubkey;
+
+    // Mint A is allowed, mint B has no entry
+    let mut mint_a_entry_account = AccountSharedData::new(
+        mollusk
+            .sysvars
+            .rent
+            .minimum_balance(mem::size_of::<MintEntry>()),
+        mem::size_of::<MintEntry>(),
+        &crate::ID,
+    );
+    mint_a_entry_account.set_data_from_slice(bytes_of::<MintEntry>(&MintEntry {
+        status: MINT_STATUS_ALLOWED,
+        bump: mint_a_entry_bump,
+        mint: mint_a,
+        ..Default::default()
+    }));
+
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &instruction,
+        &vec![
+            (maker, AccountSharedData::default()),
+            (mint_a, AccountSharedData::default()),
+            (mint_b, AccountSharedData::default()),
+            (maker_ta_a, AccountSharedData::default()),
+            (escrow, AccountSharedData::default()),
+            (vault, AccountSharedData::default()),
+            (token_program, token_program_account),
+            (system_program, system_program_account),
+            (
+                CONFIG_ADDRESS,
+                config_account(
+                    &mollusk,
+                    Config {
+                        flags: CONFIG_FLAG_ALLOWLIST,
+                        ..Default::default()
+                    },
+                ),
+            ),
+            (mint_a_entry, mint_a_entry_account),
+            (find_mint_entry_address(&mint_b).0, AccountSharedData::default()),
+        ],
+    );
+    assert!(matches!(
+        result.program_result,
+        ProgramResult::Failure(ProgramError::Custom(code)) if code == EscrowError::MintNotAllowed as u32
+    ));
+}
+
+#[test]
+fn set_mint_status() {
+    // Add our built program binary
+    let mollusk: Mollusk = Mollusk::new(&crate::ID, "target/deploy/native_escrow_2024");
+    let (system_program, system_program_account) = program::system_program();
+
+    // Accounts
+    let admin = Pubkey::new_from_array([0x01; 32]);
+    let mint = Pubkey::new_from_array([0x02; 32]);
+    let (entry, bump) = find_mint_entry_address(&mint);
+
+    // Admin allows the mint, creating its entry
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &set_mint_status_instruction(&admin, &mint, MINT_STATUS_ALLOWED),
+        &vec![
+            (
+                admin,
+                AccountSharedData::new(1_000_000_000, 0, &Pubkey::default()),
+            ),
+            (
+                CONFIG_ADDRESS,
+                config_account(
+                    &mollusk,
+                    Config {
+                        admin,
+                        ..Default::default()
+                    },
+                ),
+            ),
+            (mint, AccountSharedData::default()),
+            (entry, AccountSharedData::default()),
+            (system_program, system_program_account),
+        ],
+    );
+    assert!(matches!(result.program_result, ProgramResult::Success));
+
+    let entry_data =
+        bytemuck::pod_read_unaligned::<MintEntry>(resulting_account(&result, &entry).data());
+    assert_eq!(entry_data.status, MINT_STATUS_ALLOWED);
+    assert_eq!(entry_data.bump, bump);
+    assert_eq!(entry_data.mint, mint);
+}
+
+#[test]
+fn decode_event() {
+    let escrow_data = Escrow {
+        seed: 1337,
+        maker: Pubkey::new_from_array([0x01; 32]),
+        mint_a: Pubkey::new_from_array([0x02; 32]),
+        mint_b: Pubkey::new_from_array([0x03; 32]),
+        ..Default::default()
+    };
+    let event = EscrowEvent {
+        taker: Pubkey::new_from_array([0x04; 32]),
+        fee_b: 1_000,
+        ..escrow_data.event(EVENT_TAKE, &Pubkey::new_from_array([0x05; 32]), 100_000, 200_000)
+    };
+
+    // The logged bytes decode back to the event
+    assert_eq!(EscrowEvent::decode(bytes_of(&event)), Some(event));
+
+    // Unknown versions and other programs' data are skipped
+    let mut data = bytes_of(&event).to_vec();
+    data[0] = 0xff;
+    assert_eq!(EscrowEvent::decode(&data), None);
+    assert_eq!(EscrowEvent::decode(&data[..32]), None);
+}
+
+#[test]
+fn refund_with_event_cpi() {
+    // Add our built program binary
+    let mollusk: Mollusk = Mollusk::new(&crate::ID, "target/deploy/native_escrow_2024");
+
+    // Set our seed
+    let seed: u64 = 1337;
+
+    // Programs
+    let (token_program, token_program_account) = (spl_token::ID, program_account(&spl_token::ID));
+    let (system_program, system_program_account) = program::system_program();
+
+    // The precomputed event authority PDA matches its seeds
+    assert_eq!(
+        Pubkey::find_program_address(&[b"event_authority"], &crate::ID),
+        (EVENT_AUTHORITY_ADDRESS, EVENT_AUTHORITY_BUMP)
+    );
+
+    // Accounts
+    let maker = Pubkey::new_from_array([0x01; 32]);
+    let mint_a = spl_token::native_mint::ID;
+    let mint_b = Pubkey::new_from_array([0x03; 32]);
+    let (escrow, bump) = Pubkey::find_program_address(
+        &[b"escrow", maker.as_ref(), &seed.to_le_bytes()],
+        &crate::ID,
+    );
+    let vault = Pubkey::find_program_address(&[b"vault", escrow.as_ref()], &crate::ID).0;
+
+    // Create our instruction refunding SOL, recording the event in an inner instruction
+    let mut instruction = refund_instruction(&maker, &mint_a, &maker, &token_program, seed);
+    add_event_accounts(&mut instruction);
+
+    let mut escrow_account = escrow_account(
+        &mollusk,
+        Escrow {
+            seed,
+            maker,
+            mint_a,
+            mint_b,
+            receive: 100_000,
+            flags: FLAG_NATIVE_A,
+            bump,
+            ..Default::default()
+        },
+    );
+    escrow_account.set_lamports(escrow_account.lamports() + 100_000);
+
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &instruction,
+        &vec![
+            (maker, AccountSharedData::new(1_000_000_000, 0, &Pubkey::default())),
+            (mint_a, AccountSharedData::default()),
+            (escrow, escrow_account),
+            (vault, AccountSharedData::default()),
+            (token_program, token_program_account),
+            (system_program, system_program_account),
+            (EVENT_AUTHORITY_ADDRESS, AccountSharedData::default()),
+            (crate::ID, program_account(&crate::ID)),
+        ],
+    );
+    assert!(matches!(result.program_result, ProgramResult::Success));
+    assert_eq!(resulting_account(&result, &escrow).lamports(), 0);
+}
+
+#[test]
+fn emit_event_requires_event_authority() {
+    // Add our built program binary
+    let mollusk: Mollusk = Mollusk::new(&crate::ID, "target/deploy/native_escrow_2024");
+
+    // Anyone calling EmitEvent directly cannot sign as the event authority
+    let instruction = Instruction::new_with_bytes(
+        crate::ID,
+        &[0x0d],
+        vec![AccountMeta::new_readonly(EVENT_AUTHORITY_ADDRESS, false)],
+    );
+
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &instruction,
+        &vec![(EVENT_AUTHORITY_ADDRESS, AccountSharedData::default())],
+    );
+    assert!(matches!(
+        result.program_result,
+        ProgramResult::Failure(ProgramError::Custom(code)) if code == EscrowError::InvalidAuthority as u32
+    ));
+}
+
+#[test]
+fn take_auction() {
+    // Add our built program binary
+    let mut mollusk: Mollusk = Mollusk::new(&crate::ID, "target/deploy/native_escrow_2024");
+    mollusk.sysvars.clock.unix_timestamp = 1_500;
+
+    // Set our seed
+    let seed: u64 = 1337;
+
+    // Programs
+    mollusk.add_program(&spl_token::ID, "src/tests/spl_token-3.5.0");
+    let (token_program, token_program_account) = (spl_token::ID, program_account(&spl_token::ID));
+    let (system_program, system_program_account) = program::system_program();
+
+    // Accounts
+    let taker = Pubkey::new_from_array([0x04; 32]);
+    let maker = Pubkey::new_from_array([0x01; 32]);
+    let mint_a = Pubkey::new_from_array([0x02; 32]);
+    let mint_b = Pubkey::new_from_array([0x03; 32]);
+    let treasury = Pubkey::new_from_array([0x08; 32]);
+    let taker_ta_a = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &taker,
+        &mint_a,
+        &token_program,
+    );
+    let taker_ta_b = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &taker,
+        &mint_b,
+        &token_program,
+    );
+    let maker_ta_b = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &maker,
+        &mint_b,
+        &token_program,
+    );
+    let (escrow, bump) = Pubkey::find_program_address(
+        &[b"escrow", maker.as_ref(), &seed.to_le_bytes()],
+        &crate::ID,
+    );
+    let (vault, vault_bump) =
+        Pubkey::find_program_address(&[b"vault", escrow.as_ref()], &crate::ID);
+
+    // Create our instruction halfway through the auction
+    let instruction = take_instruction(
+        &taker,
+        &maker,
+        &mint_a,
+        &mint_b,
+        &taker_ta_a,
+        &taker_ta_b,
+        &maker_ta_b,
+        &treasury,
+        &token_program,
+        seed,
+    );
+
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &instruction,
+        &vec![
+            (
+                taker,
+                AccountSharedData::new(1_000_000_000, 0, &Pubkey::default()),
+            ),
+            (
+                maker,
+                AccountSharedData::new(1_000_000_000, 0, &Pubkey::default()),
+            ),
+            (mint_a, mint_account(&mollusk, Pubkey::new_from_array([0x05; 32]))),
+            (mint_b, mint_account(&mollusk, Pubkey::new_from_array([0x06; 32]))),
+            (taker_ta_a, token_account(&mollusk, mint_a, taker, 0)),
+            (taker_ta_b, token_account(&mollusk, mint_b, taker, 1_000_000_000)),
+            (maker_ta_b, token_account(&mollusk, mint_b, maker, 0)),
+            (
+                escrow,
+                escrow_account(
+                    &mollusk,
+                    Escrow {
+                        seed,
+                        maker,
+                        mint_a,
+                        mint_b,
+                        receive: 200_000,
+                        auction_start: 1_000,
+                        auction_end: 2_000,
+                        auction_end_receive: 100_000,
+                        bump,
+                        vault_bump,
+                        ..Default::default()
+                    },
+                ),
+            ),
+            (vault, token_account(&mollusk, mint_a, escrow, 100_000)),
+            (token_program, token_program_account),
+            (system_program, system_program_account),
+            (CONFIG_ADDRESS, AccountSharedData::default()),
+            (treasury, AccountSharedData::default()),
+        ],
+    );
+    assert!(matches!(result.program_result, ProgramResult::Success));
+
+    // The price has decayed halfway to the end price
+    assert_eq!(
+        spl_token::state::Account::unpack(resulting_account(&result, &maker_ta_b).data())
+            .unwrap()
+            .amount,
+        150_000
+    );
+}
+
+#[test]
+fn make_rejects_truncated_payload() {
+    let make = Make {
+        seed: 1337,
+        amount: 100_000,
+        receive: 100_000,
+        auction_end: 2_000,
+        ..Default::default()
+    };
+
+    // Only the full payload is accepted
+    assert!(Make::try_from(bytes_of(&make)) == Ok(make));
+
+    // Payloads cut short anywhere are rejected rather than read with zeroed fields
+    assert!(Make::try_from(&bytes_of(&make)[..24]).is_err());
+    assert!(Make::try_from(&bytes_of(&make)[..72]).is_err());
+    assert!(Make::try_from(&bytes_of(&make)[..mem::size_of::<Make>() - 1]).is_err());
+}
+
+#[test]
+fn take_oracle() {
+    // Add our built program binary
+    let mut mollusk: Mollusk = Mollusk::new(&crate::ID, "target/deploy/native_escrow_2024");
+    mollusk.sysvars.clock.unix_timestamp = 1_000;
+
+    // Set our seed
+    let seed: u64 = 1337;
+
+    // Programs
+    mollusk.add_program(&spl_token::ID, "src/tests/spl_token-3.5.0");
+    let (token_program, token_program_account) = (spl_token::ID, program_account(&spl_token::ID));
+    let (system_program, system_program_account) = program::system_program();
+
+    // Accounts
+    let taker = Pubkey::new_from_array([0x04; 32]);
+    let maker = Pubkey::new_from_array([0x01; 32]);
+    let mint_a = Pubkey::new_from_array([0x02; 32]);
+    let mint_b = Pubkey::new_from_array([0x03; 32]);
+    let treasury = Pubkey::new_from_array([0x08; 32]);
+    let price_feed = Pubkey::new_from_array([0x09; 32]);
+    let taker_ta_a = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &taker,
+        &mint_a,
+        &token_program,
+    );
+    let taker_ta_b = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &taker,
+        &mint_b,
+        &token_program,
+    );
+    let maker_ta_b = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &maker,
+        &mint_b,
+        &token_program,
+    );
+    let (escrow, bump) = Pubkey::find_program_address(
+        &[b"escrow", maker.as_ref(), &seed.to_le_bytes()],
+        &crate::ID,
+    );
+    let (vault, vault_bump) =
+        Pubkey::find_program_address(&[b"vault", escrow.as_ref()], &crate::ID);
+
+    // Mock feed pricing 1 token A at 2.5 token B, published 10 seconds ago
+    let mut price_feed_account = AccountSharedData::new(1_000_000_000, mem::size_of::<PriceFeed>(), &Pubkey::default());
+    price_feed_account.set_data_from_slice(bytes_of(&PriceFeed {
+        price: 25,
+        exponent: 1,
+        published_at: 990,
+        ..Default::default()
+    }));
+
+    // Create our instruction, appending the price feed
+    let mut instruction = take_instruction(
+        &taker,
+        &maker,
+        &mint_a,
+        &mint_b,
+        &taker_ta_a,
+        &taker_ta_b,
+        &maker_ta_b,
+        &treasury,
+        &token_program,
+        seed,
+    );
+    instruction.accounts.push(AccountMeta::new_readonly(price_feed, false));
+
+    // Escrow pegged to the feed at a 1% discount
+    let accounts = vec![
+        (
+            taker,
+            AccountSharedData::new(1_000_000_000, 0, &Pubkey::default()),
+        ),
+        (
+            maker,
+            AccountSharedData::new(1_000_000_000, 0, &Pubkey::default()),
+        ),
+        (mint_a, mint_account(&mollusk, Pubkey::new_from_array([0x05; 32]))),
+        (mint_b, mint_account(&mollusk, Pubkey::new_from_array([0x06; 32]))),
+        (taker_ta_a, token_account(&mollusk, mint_a, taker, 0)),
+        (taker_ta_b, token_account(&mollusk, mint_b, taker, 1_000_000_000)),
+        (maker_ta_b, token_account(&mollusk, mint_b, maker, 0)),
+        (
+            escrow,
+            escrow_account(
+                &mollusk,
+                Escrow {
+                    seed,
+                    maker,
+                    mint_a,
+                    mint_b,
+                    price_feed,
+                    max_staleness: 60,
+                    premium_bps: -100,
+                    bump,
+                    vault_bump,
+                    ..Default::default()
+                },
+            ),
+        ),
+        (vault, token_account(&mollusk, mint_a, escrow, 100_000)),
+        (token_program, token_program_account),
+        (system_program, system_program_account),
+        (CONFIG_ADDRESS, AccountSharedData::default()),
+        (treasury, AccountSharedData::default()),
+        (price_feed, price_feed_account),
+    ];
+
+    let result: mollusk_svm::result::InstructionResult =
+        mollusk.process_instruction(&instruction, &accounts);
+    assert!(matches!(result.program_result, ProgramResult::Success));
+
+    // The maker is paid the feed price less the discount
+    assert_eq!(
+        spl_token::state::Account::unpack(resulting_account(&result, &maker_ta_b).data())
+            .unwrap()
+            .amount,
+        247_500
+    );
+
+    // A price older than the staleness bound is rejected
+    mollusk.sysvars.clock.unix_timestamp = 1_100;
+    let result: mollusk_svm::result::InstructionResult =
+        mollusk.process_instruction(&instruction, &accounts);
+    assert!(matches!(
+        result.program_result,
+        ProgramResult::Failure(ProgramError::Custom(code)) if code == EscrowError::StalePrice as u32
+    ));
+
+    // A price published in the future is rejected
+    mollusk.sysvars.clock.unix_timestamp = 980;
+    let result: mollusk_svm::result::InstructionResult =
+        mollusk.process_instruction(&instruction, &accounts);
+    assert!(matches!(
+        result.program_result,
+        ProgramResult::Failure(ProgramError::Custom(code)) if code == EscrowError::InvalidPriceFeed as u32
+    ));
+
+    // A zero price is rejected
+    mollusk.sysvars.clock.unix_timestamp = 1_000;
+    let mut zero_price_accounts = accounts.clone();
+    zero_price_accounts.last_mut().unwrap().1.set_data_from_slice(bytes_of(&PriceFeed {
+        price: 0,
+        exponent: 1,
+        published_at: 990,
+        ..Default::default()
+    }));
+    let result: mollusk_svm::result::InstructionResult =
+        mollusk.process_instruction(&instruction, &zero_price_accounts);
+    assert!(matches!(
+        result.program_result,
+        ProgramResult::Failure(ProgramError::Custom(code)) if code == EscrowError::InvalidPriceFeed as u32
+    ));
+
+    // A premium pushing the price past u128 fails instead of wrapping
+    let mut overflow_accounts = accounts.clone();
+    overflow_accounts[8].1 = token_account(&mollusk, mint_a, escrow, u64::MAX);
+    overflow_accounts.last_mut().unwrap().1.set_data_from_slice(bytes_of(&PriceFeed {
+        price: u64::MAX,
+        exponent: 0,
+        published_at: 990,
+        ..Default::default()
+    }));
+    let result: mollusk_svm::result::InstructionResult =
+        mollusk.process_instruction(&instruction, &overflow_accounts);
+    assert!(matches!(
+        result.program_result,
+        ProgramResult::Failure(ProgramError::ArithmeticOverflow)
+    ));
+}
+
+#[test]
+fn claim() {
+    // Add our built program binary
+    let mut mollusk: Mollusk = Mollusk::new(&crate::ID, "target/deploy/native_escrow_2024");
+    mollusk.sysvars.clock.unix_timestamp = 1_000;
+
+    // Set our seed
+    let seed: u64 = 1337;
+
+    // Programs
+    mollusk.add_program(&spl_token::ID, "src/tests/spl_token-3.5.0");
+    let (token_program, token_program_account) = (spl_token::ID, program_account(&spl_token::ID));
+    let (system_program, system_program_account) = program::system_program();
+
+    // Accounts
+    let taker = Pubkey::new_from_array([0x04; 32]);
+    let maker = Pubkey::new_from_array([0x01; 32]);
+    let mint_a = Pubkey::new_from_array([0x02; 32]);
+    let mint_b = Pubkey::new_from_array([0x03; 32]);
+    let taker_ta_a = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &taker,
+        &mint_a,
+        &token_program,
+    );
+    let (escrow, bump) = Pubkey::find_program_address(
+        &[b"escrow", maker.as_ref(), &seed.to_le_bytes()],
+        &crate::ID,
+    );
+    let (vault, vault_bump) =
+        Pubkey::find_program_address(&[b"vault", escrow.as_ref()], &crate::ID);
+
+    // Escrow locked to the hash of the preimage until its timeout
+    let preimage = [0x07; 32];
+    let escrow_account = escrow_account(
+        &mollusk,
+        Escrow {
+            seed,
+            maker,
+            mint_a,
+            mint_b,
+            taker,
+            expires_at: 2_000,
+            hashlock: solana_sdk::hash::hash(&preimage).to_bytes(),
+            bump,
+            vault_bump,
+            ..Default::default()
+        },
+    );
+
+    // A wrong preimage is rejected
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &claim_instruction(&maker, &mint_a, &taker_ta_a, &token_program, seed, [0x08; 32]),
+        &vec![
+            (maker, AccountSharedData::default()),
+            (mint_a, AccountSharedData::default()),
+            (taker_ta_a, AccountSharedData::default()),
+            (escrow, escrow_account.clone()),
+            (vault, AccountSharedData::default()),
+            (token_program, token_program_account.clone()),
+            (system_program, system_program_account.clone()),
+        ],
+    );
+    assert!(matches!(
+        result.program_result,
+        ProgramResult::Failure(ProgramError::Custom(code)) if code == EscrowError::InvalidPreimage as u32
+    ));
+
+    // A claim on an escrow still awaiting approvals is rejected, as a Take would be
+    let mut pending_escrow_data = bytemuck::pod_read_unaligned::<Escrow>(escrow_account.data());
+    pending_escrow_data.approvers[0] = Pubkey::new_from_array([0x0b; 32]);
+    pending_escrow_data.threshold = 1;
+    let mut pending_escrow_account = escrow_account.clone();
+    pending_escrow_account.set_data_from_slice(bytes_of::<Escrow>(&pending_escrow_data));
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &claim_instruction(&maker, &mint_a, &taker_ta_a, &token_program, seed, preimage),
+        &vec![
+            (maker, AccountSharedData::default()),
+            (mint_a, AccountSharedData::default()),
+            (taker_ta_a, AccountSharedData::default()),
+            (escrow, pending_escrow_account),
+            (vault, AccountSharedData::default()),
+            (token_program, token_program_account.clone()),
+            (system_program, system_program_account.clone()),
+        ],
+    );
+    assert!(matches!(
+        result.program_result,
+        ProgramResult::Failure(ProgramError::Custom(code)) if code == EscrowError::ApprovalsPending as u32
+    ));
+
+    // The maker cannot refund before the timeout
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &refund_instruction(&maker, &mint_a, &taker_ta_a, &token_program, seed),
+        &vec![
+            (maker, AccountSharedData::default()),
+            (mint_a, AccountSharedData::default()),
+            (taker_ta_a, AccountSharedData::default()),
+            (escrow, escrow_account.clone()),
+            (vault, AccountSharedData::default()),
+            (token_program, token_program_account.clone()),
+            (system_program, system_program_account.clone()),
+        ],
+    );
+    assert!(matches!(
+        result.program_result,
+        ProgramResult::Failure(ProgramError::Custom(code)) if code == EscrowError::EscrowNotExpired as u32
+    ));
+
+    // Anyone presenting the preimage releases the vault to the taker
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &claim_instruction(&maker, &mint_a, &taker_ta_a, &token_program, seed, preimage),
+        &vec![
+            (
+                maker,
+                AccountSharedData::new(1_000_000_000, 0, &Pubkey::default()),
+            ),
+            (mint_a, mint_account(&mollusk, Pubkey::new_from_array([0x05; 32]))),
+            (taker_ta_a, token_account(&mollusk, mint_a, taker, 0)),
+            (escrow, escrow_account),
+            (vault, token_account(&mollusk, mint_a, escrow, 100_000)),
+            (token_program, token_program_account),
+            (system_program, system_program_account),
+        ],
+    );
+    assert!(matches!(result.program_result, ProgramResult::Success));
+    assert_eq!(
+        spl_token::state::Account::unpack(resulting_account(&result, &taker_ta_a).data())
+            .unwrap()
+            .amount,
+        100_000
+    );
+}
+
+#[test]
+fn approve() {
+    // Add our built program binary
+    let mollusk: Mollusk = Mollusk::new(&crate::ID, "target/deploy/native_escrow_2024");
+    let (token_program, token_program_account) = (spl_token::ID, program_account(&spl_token::ID));
+    let (system_program, system_program_account) = program::system_program();
+
+    // Set our seed
+    let seed: u64 = 1337;
+
+    // Accounts
+    let taker = Pubkey::new_from_array([0x04; 32]);
+    let maker = Pubkey::new_from_array([0x01; 32]);
+    let mint_a = Pubkey::new_from_array([0x02; 32]);
+    let mint_b = Pubkey::new_from_array([0x03; 32]);
+    let treasury = Pubkey::new_from_array([0x08; 32]);
+    let approver = Pubkey::new_from_array([0x0b; 32]);
+    let (escrow, bump) = Pubkey::find_program_address(
+        &[b"escrow", maker.as_ref(), &seed.to_le_bytes()],
+        &crate::ID,
+    );
+
+    // Escrow requiring 2-of-3 approvals, one of them given
+    let escrow_account = escrow_account(
+        &mollusk,
+        Escrow {
+            seed,
+            maker,
+            mint_a,
+            mint_b,
+            receive: 100_000,
+            approvers: [
+                Pubkey::new_from_array([0x0a; 32]),
+                approver,
+                Pubkey::new_from_array([0x0c; 32]),
+                Pubkey::default(),
+                Pubkey::default(),
+            ],
+            approvals: 0b001,
+            threshold: 2,
+            bump,
+            ..Default::default()
+        },
+    );
+
+    // Take is rejected until the threshold is met
+    let instruction = take_instruction(
+        &taker,
+        &maker,
+        &mint_a,
+        &mint_b,
+        &Pubkey::new_from_array([0x05; 32]),
+        &Pubkey::new_from_array([0x06; 32]),
+        &Pubkey::new_from_array([0x07; 32]),
+        &treasury,
+        &token_program,
+        seed,
+    );
+    let mut accounts: Vec<(Pubkey, AccountSharedData)> = instruction
+        .accounts
+        .iter()
+        .map(|meta| (meta.pubkey, AccountSharedData::default()))
+        .collect();
+    accounts[7].1 = escrow_account.clone();
+    accounts[9].1 = token_program_account;
+    accounts[10].1 = system_program_account;
+    let result: mollusk_svm::result::InstructionResult =
+        mollusk.process_instruction(&instruction, &accounts);
+    assert!(matches!(
+        result.program_result,
+        ProgramResult::Failure(ProgramError::Custom(code)) if code == EscrowError::ApprovalsPending as u32
+    ));
+
+    // Only approvers can approve
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &approve_instruction(&taker, &maker, seed),
+        &vec![
+            (taker, AccountSharedData::default()),
+            (maker, AccountSharedData::default()),
+            (escrow, escrow_account.clone()),
+        ],
+    );
+    assert!(matches!(
+        result.program_result,
+        ProgramResult::Failure(ProgramError::Custom(code)) if code == EscrowError::InvalidApprover as u32
+    ));
+
+    // The second approval meets the threshold
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &approve_instruction(&approver, &maker, seed),
+        &vec![
+            (approver, AccountSharedData::default()),
+            (maker, AccountSharedData::default()),
+            (escrow, escrow_account),
+        ],
+    );
+    assert!(matches!(result.program_result, ProgramResult::Success));
+    let escrow_data = bytemuck::pod_read_unaligned::<Escrow>(resulting_account(&result, &escrow).data());
+    assert_eq!(escrow_data.approvals, 0b011);
+    assert!(escrow_data.is_approved());
+}
+
+#[test]
+fn resolve() {
+    // Add our built program binary
+    let mut mollusk: Mollusk = Mollusk::new(&crate::ID, "target/deploy/native_escrow_2024");
+
+    // Set our seed
+    let seed: u64 = 1337;
+
+    // Programs
+    mollusk.add_program(&spl_token::ID, "src/tests/spl_token-3.5.0");
+    let (token_program, token_program_account) = (spl_token::ID, program_account(&spl_token::ID));
+    let (system_program, system_program_account) = program::system_program();
+
+    // Accounts
+    let taker = Pubkey::new_from_array([0x04; 32]);
+    let maker = Pubkey::new_from_array([0x01; 32]);
+    let mint_a = Pubkey::new_from_array([0x02; 32]);
+    let mint_b = Pubkey::new_from_array([0x03; 32]);
+    let arbiter = Pubkey::new_from_array([0x09; 32]);
+    let maker_ta_a = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &maker,
+        &mint_a,
+        &token_program,
+    );
+    let taker_ta_a = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &taker,
+        &mint_a,
+        &token_program,
+    );
+    let (escrow, bump) = Pubkey::find_program_address(
+        &[b"escrow", maker.as_ref(), &seed.to_le_bytes()],
+        &crate::ID,
+    );
+    let (vault, vault_bump) =
+        Pubkey::find_program_address(&[b"vault", escrow.as_ref()], &crate::ID);
+
+    // Escrow with an arbiter between the maker and the taker
+    let escrow_account = escrow_account(
+        &mollusk,
+        Escrow {
+            seed,
+            maker,
+            mint_a,
+            mint_b,
+            receive: 100_000,
+            taker,
+            arbiter,
+            bump,
+            vault_bump,
+            ..Default::default()
+        },
+    );
+
+    // The taker disputes the escrow
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &dispute_instruction(&taker, &maker, seed),
+        &vec![
+            (taker, AccountSharedData::default()),
+            (maker, AccountSharedData::default()),
+            (escrow, escrow_account),
+        ],
+    );
+    assert!(matches!(result.program_result, ProgramResult::Success));
+    let escrow_account = resulting_account(&result, &escrow).clone();
+    let escrow_data = bytemuck::pod_read_unaligned::<Escrow>(escrow_account.data());
+    assert_eq!(escrow_data.disputed, 1);
+
+    // The maker can no longer refund a disputed escrow
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &refund_instruction(&maker, &mint_a, &maker_ta_a, &token_program, seed),
+        &vec![
+            (maker, AccountSharedData::default()),
+            (mint_a, AccountSharedData::default()),
+            (maker_ta_a, AccountSharedData::default()),
+            (escrow, escrow_account.clone()),
+            (vault, AccountSharedData::default()),
+            (token_program, token_program_account.clone()),
+            (system_program, system_program_account.clone()),
+        ],
+    );
+    assert!(matches!(
+        result.program_result,
+        ProgramResult::Failure(ProgramError::Custom(code)) if code == EscrowError::EscrowDisputed as u32
+    ));
+
+    // The arbiter splits the vault between the maker and the taker
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &resolve_instruction(
+            &arbiter,
+            &maker,
+            &mint_a,
+            &maker_ta_a,
+            &taker_ta_a,
+            &token_program,
+            seed,
+            Resolve {
+                maker_amount: 40_000,
+                taker_amount: 60_000,
+            },
+        ),
+        &vec![
+            (arbiter, AccountSharedData::default()),
+            (
+                maker,
+                AccountSharedData::new(1_000_000_000, 0, &Pubkey::default()),
+            ),
+            (mint_a, mint_account(&mollusk, Pubkey::new_from_array([0x05; 32]))),
+            (maker_ta_a, token_account(&mollusk, mint_a, maker, 0)),
+            (taker_ta_a, token_account(&mollusk, mint_a, taker, 0)),
+            (escrow, escrow_account),
+            (vault, token_account(&mollusk, mint_a, escrow, 100_000)),
+            (token_program, token_program_account),
+            (system_program, system_program_account),
+        ],
+    );
+    assert!(matches!(result.program_result, ProgramResult::Success));
+    assert_eq!(
+        spl_token::state::Account::unpack(resulting_account(&result, &maker_ta_a).data())
+            .unwrap()
+            .amount,
+        40_000
+    );
+    assert_eq!(
+        spl_token::state::Account::unpack(resulting_account(&result, &taker_ta_a).data())
+            .unwrap()
+            .amount,
+        60_000
+    );
+}
+
+#[test]
+fn arbiter_blocks_maker_until_expiry() {
+    // Add our built program binary
+    let mut mollusk: Mollusk = Mollusk::new(&crate::ID, "target/deploy/native_escrow_2024");
+    mollusk.sysvars.clock.unix_timestamp = 1_000;
+
+    // Set our seed
+    let seed: u64 = 1337;
+
+    // Programs
+    mollusk.add_program(&spl_token::ID, "src/tests/spl_token-3.5.0");
+    let (token_program, token_program_account) = (spl_token::ID, program_account(&spl_token::ID));
+    let (system_program, system_program_account) = program::system_program();
+
+    // Accounts
+    let taker = Pubkey::new_from_array([0x04; 32]);
+    let maker = Pubkey::new_from_array([0x01; 32]);
+    let mint_a = Pubkey::new_from_array([0x02; 32]);
+    let mint_b = Pubkey::new_from_array([0x03; 32]);
+    let arbiter = Pubkey::new_from_array([0x09; 32]);
+    let maker_ta_a = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &maker,
+        &mint_a,
+        &token_program,
+    );
+    let (escrow, bump) = Pubkey::find_program_address(
+        &[b"escrow", maker.as_ref(), &seed.to_le_bytes()],
+        &crate::ID,
+    );
+    let (vault, vault_bump) =
+        Pubkey::find_program_address(&[b"vault", escrow.as_ref()], &crate::ID);
+
+    // Escrow with an arbiter between the maker and the taker, expiring at 2,000
+    let escrow_account = escrow_account(
+        &mollusk,
+        Escrow {
+            seed,
+            maker,
+            mint_a,
+            mint_b,
+            receive: 100_000,
+            taker,
+            arbiter,
+            expires_at: 2_000,
+            bump,
+            vault_bump,
+            ..Default::default()
+        },
+    );
+    let accounts = vec![
+        (
+            maker,
+            AccountSharedData::new(1_000_000_000, 0, &Pubkey::default()),
+        ),
+        (mint_a, mint_account(&mollusk, Pubkey::new_from_array([0x05; 32]))),
+        (maker_ta_a, token_account(&mollusk, mint_a, maker, 0)),
+        (escrow, escrow_account.clone()),
+        (vault, token_account(&mollusk, mint_a, escrow, 100_000)),
+        (token_program, token_program_account),
+        (system_program, system_program_account),
+    ];
+
+    // The maker cannot refund, withdraw or reprice on their own before expiry, front-running a dispute
+    for instruction in [
+        refund_instruction(&maker, &mint_a, &maker_ta_a, &token_program, seed),
+        withdraw_instruction(&maker, &mint_a, &maker_ta_a, &token_program, seed, 50_000),
+    ] {
+        let result: mollusk_svm::result::InstructionResult =
+            mollusk.process_instruction(&instruction, &accounts);
+        assert!(matches!(
+            result.program_result,
+            ProgramResult::Failure(ProgramError::Custom(code)) if code == EscrowError::ArbitratedEscrow as u32
+        ));
+    }
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &update_instruction(
+            &maker,
+            &mint_b,
+            seed,
+            Update {
+                receive: 50_000,
+                ..Default::default()
+            },
+        ),
+        &vec![
+            (maker, AccountSharedData::default()),
+            (mint_b, mint_account(&mollusk, Pubkey::new_from_array([0x06; 32]))),
+            (escrow, escrow_account),
+            (CONFIG_ADDRESS, AccountSharedData::default()),
+            (find_mint_entry_address(&mint_b).0, AccountSharedData::default()),
+        ],
+    );
+    assert!(matches!(
+        result.program_result,
+        ProgramResult::Failure(ProgramError::Custom(code)) if code == EscrowError::ArbitratedEscrow as u32
+    ));
+
+    // Once expired, the maker refunds the vault
+    mollusk.sysvars.clock.unix_timestamp = 2_000;
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &refund_instruction(&maker, &mint_a, &maker_ta_a, &token_program, seed),
+        &accounts,
+    );
+    assert!(matches!(result.program_result, ProgramResult::Success));
+    assert_eq!(
+        spl_token::state::Account::unpack(resulting_account(&result, &maker_ta_a).data())
+            .unwrap()
+            .amount,
+        100_000
+    );
+}
+
+#[test]
+fn token_2022() {
+    // Add our built program binary
+    let mut mollusk: Mollusk = Mollusk::new(&crate::ID, "target/deploy/native_escrow_2024");
+
+    // Set our seed
+    let seed: u64 = 1337;
+
+    // Programs
+    mollusk_svm_programs_token::token2022::add_program(&mut mollusk);
+    let (token_program, token_program_account) = (spl_token_2022::ID, program_account(&spl_token_2022::ID));
+    let (system_program, system_program_account) = program::system_program();
+
+    // Accounts
+    let taker = Pubkey::new_from_array([0x04; 32]);
+    let maker = Pubkey::new_from_array([0x01; 32]);
+    let mint_a = Pubkey::new_from_array([0x02; 32]);
+    let mint_b = Pubkey::new_from_array([0x03; 32]);
+    let treasury = Pubkey::new_from_array([0x08; 32]);
+    let maker_ta_a = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &maker,
+        &mint_a,
+        &token_program,
+    );
+    let maker_ta_b = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &maker,
+        &mint_b,
+        &token_program,
+    );
+    let taker_ta_a = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &taker,
+        &mint_a,
+        &token_program,
+    );
+    let taker_ta_b = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &taker,
+        &mint_b,
+        &token_program,
+    );
+    let escrow = Pubkey::find_program_address(
+        &[b"escrow", maker.as_ref(), &seed.to_le_bytes()],
+        &crate::ID,
+    )
+    .0;
+    let vault = Pubkey::find_program_address(&[b"vault", escrow.as_ref()], &crate::ID).0;
+
+    // Mints carrying extensions
+    let mint_a_account = mint_2022_account(
+        &mollusk,
+        Pubkey::new_from_array([0x05; 32]),
+        &[ExtensionType::MetadataPointer],
+        |mint| {
+            mint.init_extension::<MetadataPointer>(true).unwrap();
+        },
+    );
+    let mint_b_account = mint_2022_account(
+        &mollusk,
+        Pubkey::new_from_array([0x06; 32]),
+        &[ExtensionType::InterestBearingConfig],
+        |mint| {
+            mint.init_extension::<InterestBearingConfig>(true).unwrap();
+        },
+    );
+
+    // Make creates the vault under Token-2022 and deposits into it
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &make_instruction(
+            &maker,
+            &mint_a,
+            &mint_b,
+            &maker_ta_a,
+            &token_program,
+            Make {
+                seed,
+                amount: 100_000,
+                receive: 100_000,
+                ..Default::default()
+            },
+        ),
+        &vec![
+            (
+                maker,
+                AccountSharedData::new(1_000_000_000, 0, &Pubkey::default()),
+            ),
+            (mint_a, mint_a_account.clone()),
+            (mint_b, mint_b_account.clone()),
+            (maker_ta_a, token_2022_account(&mollusk, &[], mint_a, maker, 1_000_000_000)),
+            (escrow, AccountSharedData::default()),
+            (vault, AccountSharedData::default()),
+            (token_program, token_program_account.clone()),
+            (system_program, system_program_account.clone()),
+            (CONFIG_ADDRESS, AccountSharedData::default()),
+            (find_mint_entry_address(&mint_a).0, AccountSharedData::default()),
+            (find_mint_entry_address(&mint_b).0, AccountSharedData::default()),
+        ],
+    );
+    assert!(matches!(result.program_result, ProgramResult::Success));
+    assert_eq!(token_2022_amount(&result, &vault), 100_000);
+
+    // Take settles both sides under Token-2022 and closes the vault
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &take_instruction(
+            &taker,
+            &maker,
+            &mint_a,
+            &mint_b,
+            &taker_ta_a,
+            &taker_ta_b,
+            &maker_ta_b,
+            &treasury,
+            &token_program,
+            seed,
+        ),
+        &vec![
+            (
+                taker,
+                AccountSharedData::new(1_000_000_000, 0, &Pubkey::default()),
+            ),
+            (maker, resulting_account(&result, &maker).clone()),
+            (mint_a, mint_a_account),
+            (mint_b, mint_b_account),
+            (taker_ta_a, token_2022_account(&mollusk, &[], mint_a, taker, 0)),
+            (taker_ta_b, token_2022_account(&mollusk, &[], mint_b, taker, 100_000)),
+            (maker_ta_b, token_2022_account(&mollusk, &[], mint_b, maker, 0)),
+            (escrow, resulting_account(&result, &escrow).clone()),
+            (vault, resulting_account(&result, &vault).clone()),
+            (token_program, token_program_account),
+            (system_program, system_program_account),
+            (CONFIG_ADDRESS, AccountSharedData::default()),
+            (treasury, AccountSharedData::default()),
+        ],
+    );
+    assert!(matches!(result.program_result, ProgramResult::Success));
+    assert_eq!(token_2022_amount(&result, &taker_ta_a), 100_000);
+    assert_eq!(token_2022_amount(&result, &maker_ta_b), 100_000);
+    assert_eq!(resulting_account(&result, &vault).lamports(), 0);
+}
+
+#[test]
+fn transfer_fee() {
+    // Add our built program binary
+    let mut mollusk: Mollusk = Mollusk::new(&crate::ID, "target/deploy/native_escrow_2024");
+
+    // Set our seed
+    let seed: u64 = 1337;
+
+    // Programs
+    mollusk_svm_programs_token::token2022::add_program(&mut mollusk);
+    let (token_program, token_program_account) = (spl_token_2022::ID, program_account(&spl_token_2022::ID));
+    let (system_program, system_program_account) = program::system_program();
+
+    // Accounts
+    let taker = Pubkey::new_from_array([0x04; 32]);
+    let maker = Pubkey::new_from_array([0x01; 32]);
+    let mint_a = Pubkey::new_from_array([0x02; 32]);
+    let mint_b = Pubkey::new_from_array([0x03; 32]);
+    let treasury = Pubkey::new_from_array([0x08; 32]);
+    let maker_ta_a = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &maker,
+        &mint_a,
+        &token_program,
+    );
+    let maker_ta_b = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &maker,
+        &mint_b,
+        &token_program,
+    );
+    let taker_ta_a = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &taker,
+        &mint_a,
+        &token_program,
+    );
+    let taker_ta_b = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &taker,
+        &mint_b,
+        &token_program,
+    );
+    let escrow = Pubkey::find_program_address(
+        &[b"escrow", maker.as_ref(), &seed.to_le_bytes()],
+        &crate::ID,
+    )
+    .0;
+    let vault = Pubkey::find_program_address(&[b"vault", escrow.as_ref()], &crate::ID).0;
+
+    // Both mints charge a 1% transfer fee
+    let extension_types = [ExtensionType::TransferFeeConfig];
+    let fee_mint_account = |mint_authority| {
+        mint_2022_account(&mollusk, mint_authority, &extension_types, |mint| {
+            let transfer_fee = TransferFee {
+                epoch: 0.into(),
+                maximum_fee: u64::MAX.into(),
+                transfer_fee_basis_points: 100.into(),
+            };
+            let transfer_fee_config = mint.init_extension::<TransferFeeConfig>(true).unwrap();
+            transfer_fee_config.older_transfer_fee = transfer_fee;
+            transfer_fee_config.newer_transfer_fee = transfer_fee;
+        })
+    };
+    let mint_a_account = fee_mint_account(Pubkey::new_from_array([0x05; 32]));
+    let mint_b_account = fee_mint_account(Pubkey::new_from_array([0x06; 32]));
+
+    // Maker receives receive less the fee by default, or all of it with the taker grossing up the payment
+    for (flags, maker_receives) in [(0, 99_000), (FLAG_RECEIVE_NET_OF_FEE, 100_000)] {
+        // The vault receives the deposit less mint A's fee
+        let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+            &make_instruction(
+                &maker,
+                &mint_a,
+                &mint_b,
+                &maker_ta_a,
+                &token_program,
+                Make {
+                    seed,
+                    amount: 100_000,
+                    receive: 100_000,
+                    flags,
+                    ..Default::default()
+                },
+            ),
+            &vec![
+                (
+                    maker,
+                    AccountSharedData::new(1_000_000_000, 0, &Pubkey::default()),
+                ),
+                (mint_a, mint_a_account.clone()),
+                (mint_b, mint_b_account.clone()),
+                (maker_ta_a, token_2022_account(&mollusk, &extension_types, mint_a, maker, 1_000_000_000)),
+                (escrow, AccountSharedData::default()),
+                (vault, AccountSharedData::default()),
+                (token_program, token_program_account.clone()),
+                (system_program, system_program_account.clone()),
+                (CONFIG_ADDRESS, AccountSharedData::default()),
+                (find_mint_entry_address(&mint_a).0, AccountSharedData::default()),
+                (find_mint_entry_address(&mint_b).0, AccountSharedData::default()),
+            ],
+        );
+        assert!(matches!(result.program_result, ProgramResult::Success));
+        assert_eq!(token_2022_amount(&result, &vault), 99_000);
+
+        // The taker claims the vault less mint A's fee, and the fees withheld in the vault are harvested to close it
+        let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+            &take_instruction(
+                &taker,
+                &maker,
+                &mint_a,
+                &mint_b,
+                &taker_ta_a,
+                &taker_ta_b,
+                &maker_ta_b,
+                &treasury,
+                &token_program,
+                seed,
+            ),
+            &vec![
+                (
+                    taker,
+                    AccountSharedData::new(1_000_000_000, 0, &Pubkey::default()),
+                ),
+                (maker, resulting_account(&result, &maker).clone()),
+                (mint_a, resulting_account(&result, &mint_a).clone()),
+                (mint_b, mint_b_account.clone()),
+                (taker_ta_a, token_2022_account(&mollusk, &extension_types, mint_a, taker, 0)),
+                (taker_ta_b, token_2022_account(&mollusk, &extension_types, mint_b, taker, 200_000)),
+                (maker_ta_b, token_2022_account(&mollusk, &extension_types, mint_b, maker, 0)),
+                (escrow, resulting_account(&result, &escrow).clone()),
+                (vault, resulting_account(&result, &vault).clone()),
+                (token_program, token_program_account.clone()),
+                (system_program, system_program_account.clone()),
+                (CONFIG_ADDRESS, AccountSharedData::default()),
+                (treasury, AccountSharedData::default()),
+            ],
+        );
+        assert!(matches!(result.program_result, ProgramResult::Success));
+        assert_eq!(token_2022_amount(&result, &maker_ta_b), maker_receives);
+        assert_eq!(token_2022_amount(&result, &taker_ta_a), 98_010);
+        assert_eq!(resulting_account(&result, &vault).lamports(), 0);
+    }
+}
+
+#[test]
+fn transfer_hook_accounts() {
+    // Add our built program binary
+    let mollusk: Mollusk = Mollusk::new(&crate::ID, "target/deploy/native_escrow_2024");
+
+    // Set our seed
+    let seed: u64 = 1337;
+
+    // Accounts
+    let token_program = spl_token_2022::ID;
+    let taker = Pubkey::new_from_array([0x04; 32]);
+    let maker = Pubkey::new_from_array([0x01; 32]);
+    let mint_a = Pubkey::new_from_array([0x02; 32]);
+    let mint_b = Pubkey::new_from_array([0x03; 32]);
+    let treasury = Pubkey::new_from_array([0x08; 32]);
+    let hook_program = Pubkey::new_from_array([0x0d; 32]);
+    let hook_account = Pubkey::new_from_array([0x0e; 32]);
+    let maker_ta_b = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &maker,
+        &mint_b,
+        &token_program,
+    );
+    let taker_ta_a = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &taker,
+        &mint_a,
+        &token_program,
+    );
+    let taker_ta_b = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &taker,
+        &mint_b,
+        &token_program,
+    );
+    let escrow = Pubkey::find_program_address(
+        &[b"escrow", maker.as_ref(), &seed.to_le_bytes()],
+        &crate::ID,
+    )
+    .0;
+    let vault = Pubkey::find_program_address(&[b"vault", escrow.as_ref()], &crate::ID).0;
+
+    // Mint A is hooked, requiring a fixed account and a writable PDA of the destination
+    let mint_a_account = mint_2022_account(
+        &mollusk,
+        Pubkey::new_from_array([0x05; 32]),
+        &[ExtensionType::TransferHook],
+        |mint| {
+            mint.init_extension::<TransferHook>(true).unwrap().program_id = Some(hook_program).try_into().unwrap();
+        },
+    );
+    let extra_account_metas = get_extra_account_metas_address(&mint_a, &hook_program);
+    let mut extra_account_metas_data = vec![0; ExtraAccountMetaList::size_of(2).unwrap()];
+    ExtraAccountMetaList::init::<ExecuteInstruction>(
+        &mut extra_account_metas_data,
+        &[
+            ExtraAccountMeta::new_with_pubkey(&hook_account, false, false).unwrap(),
+            ExtraAccountMeta::new_with_seeds(&[Seed::AccountKey { index: 2 }], false, true).unwrap(),
+        ],
+    )
+    .unwrap();
+
+    // Resolve the hook accounts for the vault transfer of a Take
+    let mut instruction = take_instruction(
+        &taker,
+        &maker,
+        &mint_a,
+        &mint_b,
+        &taker_ta_a,
+        &taker_ta_b,
+        &maker_ta_b,
+        &treasury,
+        &token_program,
+        seed,
+    );
+    let accounts_len = instruction.accounts.len();
+    add_transfer_hook_accounts(
+        &mut instruction,
+        &mint_a,
+        mint_a_account.data(),
+        &vault,
+        &taker_ta_a,
+        &escrow,
+        100_000,
+        |address| (address == &extra_account_metas).then(|| extra_account_metas_data.clone()),
+    )
+    .unwrap();
+
+    // The extra accounts are appended, followed by the hook program and its ExtraAccountMetaList
+    let destination_pda = Pubkey::find_program_address(&[taker_ta_a.as_ref()], &hook_program).0;
+    assert_eq!(
+        instruction.accounts[accounts_len..]
+            .iter()
+            .map(|meta| (meta.pubkey, meta.is_writable))
+            .collect::<Vec<_>>(),
+        vec![
+            (hook_account, false),
+            (destination_pda, true),
+            (hook_program, false),
+            (extra_account_metas, false),
+        ]
+    );
+
+    // Mints without a hook add nothing
+    let accounts_len = instruction.accounts.len();
+    add_transfer_hook_accounts(
+        &mut instruction,
+        &mint_b,
+        mint_account(&mollusk, Pubkey::new_from_array([0x06; 32])).data(),
+        &taker_ta_b,
+        &maker_ta_b,
+        &taker,
+        100_000,
+        |_| None,
+    )
+    .unwrap();
+    assert_eq!(instruction.accounts.len(), accounts_len);
+}
+
+#[test]
+fn transfer_hook() {
+    // Add our built program binary
+    let mut mollusk: Mollusk = Mollusk::new(&crate::ID, "target/deploy/native_escrow_2024");
+
+    // Set our seed
+    let seed: u64 = 1337;
+
+    // Programs, with the hook from test-programs/transfer-hook built into target/deploy
+    let hook_program = Pubkey::new_from_array([0x0d; 32]);
+    mollusk.add_program(&hook_program, "target/deploy/transfer_hook");
+    mollusk_svm_programs_token::token2022::add_program(&mut mollusk);
+    let (token_program, token_program_account) = (spl_token_2022::ID, program_account(&spl_token_2022::ID));
+    let (system_program, system_program_account) = program::system_program();
+
+    // Accounts
+    let taker = Pubkey::new_from_array([0x04; 32]);
+    let maker = Pubkey::new_from_array([0x01; 32]);
+    let mint_a = Pubkey::new_from_array([0x02; 32]);
+    let mint_b = Pubkey::new_from_array([0x03; 32]);
+    let treasury = Pubkey::new_from_array([0x08; 32]);
+    let hook_account = Pubkey::new_from_array([0x0e; 32]);
+    let maker_ta_a = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &maker,
+        &mint_a,
+        &token_program,
+    );
+    let maker_ta_b = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &maker,
+        &mint_b,
+        &token_program,
+    );
+    let taker_ta_a = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &taker,
+        &mint_a,
+        &token_program,
+    );
+    let taker_ta_b = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &taker,
+        &mint_b,
+        &token_program,
+    );
+    let escrow = Pubkey::find_program_address(
+        &[b"escrow", maker.as_ref(), &seed.to_le_bytes()],
+        &crate::ID,
+    )
+    .0;
+    let vault = Pubkey::find_program_address(&[b"vault", escrow.as_ref()], &crate::ID).0;
+
+    // Mint A is hooked, requiring a fixed account and a writable PDA of the destination
+    let mint_a_account = mint_2022_account(
+        &mollusk,
+        Pubkey::new_from_array([0x05; 32]),
+        &[ExtensionType::TransferHook],
+        |mint| {
+            mint.init_extension::<TransferHook>(true).unwrap().program_id = Some(hook_program).try_into().unwrap();
+        },
+    );
+    let mint_b_account = mint_2022_account(&mollusk, Pubkey::new_from_array([0x06; 32]), &[], |_| {});
+    let extra_account_metas = get_extra_account_metas_address(&mint_a, &hook_program);
+    let mut extra_account_metas_account = AccountSharedData::new(
+        1_000_000_000,
+        ExtraAccountMetaList::size_of(2).unwrap(),
+        &hook_program,
+    );
+    ExtraAccountMetaList::init::<ExecuteInstruction>(
+        extra_account_metas_account.data_as_mut_slice(),
+        &[
+            ExtraAccountMeta::new_with_pubkey(&hook_account, false, false).unwrap(),
+            ExtraAccountMeta::new_with_seeds(&[Seed::AccountKey { index: 2 }], false, true).unwrap(),
+        ],
+    )
+    .unwrap();
+
+    // The hook's accounts for a transfer of mint A to `destination`
+    let hook_accounts = |destination: &Pubkey| {
+        vec![
+            (hook_account, AccountSharedData::default()),
+            (
+                Pubkey::find_program_address(&[destination.as_ref()], &hook_program).0,
+                AccountSharedData::default(),
+            ),
+            (hook_program, program_account(&hook_program)),
+            (extra_account_metas, extra_account_metas_account.clone()),
+        ]
+    };
+    let get_account_data = |address: &Pubkey| {
+        (address == &extra_account_metas).then(|| extra_account_metas_account.data().to_vec())
+    };
+
+    // Make deposits through the hook, forwarding its accounts
+    let mut instruction = make_instruction(
+        &maker,
+        &mint_a,
+        &mint_b,
+        &maker_ta_a,
+        &token_program,
+        Make {
+            seed,
+            amount: 100_000,
+            receive: 100_000,
+            ..Default::default()
+        },
+    );
+    add_transfer_hook_accounts(
+        &mut instruction,
+        &mint_a,
+        mint_a_account.data(),
+        &maker_ta_a,
+        &vault,
+        &maker,
+        100_000,
+        get_account_data,
+    )
+    .unwrap();
+
+    let mut accounts = vec![
+        (
+            maker,
+            AccountSharedData::new(1_000_000_000, 0, &Pubkey::default()),
+        ),
+        (mint_a, mint_a_account.clone()),
+        (mint_b, mint_b_account.clone()),
+        (
+            maker_ta_a,
+            token_2022_account(&mollusk, &[ExtensionType::TransferHook], mint_a, maker, 1_000_000_000),
+        ),
+        (escrow, AccountSharedData::default()),
+        (vault, AccountSharedData::default()),
+        (token_program, token_program_account.clone()),
+        (system_program, system_program_account.clone()),
+        (CONFIG_ADDRESS, AccountSharedData::default()),
+        (find_mint_entry_address(&mint_a).0, AccountSharedData::default()),
+        (find_mint_entry_address(&mint_b).0, AccountSharedData::default()),
+    ];
+    accounts.extend(hook_accounts(&vault));
+
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(&instruction, &accounts);
+    assert!(matches!(result.program_result, ProgramResult::Success));
+    assert_eq!(token_2022_amount(&result, &vault), 100_000);
+
+    // Take claims the vault through the hook, signed by the escrow
+    let mut instruction = take_instruction(
+        &taker,
+        &maker,
+        &mint_a,
+        &mint_b,
+        &taker_ta_a,
+        &taker_ta_b,
+        &maker_ta_b,
+        &treasury,
+        &token_program,
+        seed,
+    );
+    add_transfer_hook_accounts(
+        &mut instruction,
+        &mint_a,
+        mint_a_account.data(),
+        &vault,
+        &taker_ta_a,
+        &escrow,
+        100_000,
+        get_account_data,
+    )
+    .unwrap();
+
+    let mut accounts = vec![
+        (
+            taker,
+            AccountSharedData::new(1_000_000_000, 0, &Pubkey::default()),
+        ),
+        (maker, resulting_account(&result, &maker).clone()),
+        (mint_a, mint_a_account.clone()),
+        (mint_b, mint_b_account),
+        (
+            taker_ta_a,
+            token_2022_account(&mollusk, &[ExtensionType::TransferHook], mint_a, taker, 0),
+        ),
+        (taker_ta_b, token_2022_account(&mollusk, &[], mint_b, taker, 100_000)),
+        (maker_ta_b, token_2022_account(&mollusk, &[], mint_b, maker, 0)),
+        (escrow, resulting_account(&result, &escrow).clone()),
+        (vault, resulting_account(&result, &vault).clone()),
+        (token_program, token_program_account.clone()),
+        (system_program, system_program_account.clone()),
+        (CONFIG_ADDRESS, AccountSharedData::default()),
+        (treasury, AccountSharedData::default()),
+    ];
+    accounts.extend(hook_accounts(&taker_ta_a));
+
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(&instruction, &accounts);
+    assert!(matches!(result.program_result, ProgramResult::Success));
+    assert_eq!(token_2022_amount(&result, &taker_ta_a), 100_000);
+    assert_eq!(resulting_account(&result, &vault).lamports(), 0);
+
+    // Without the hook's accounts the transfer is rejected
+    instruction.accounts.truncate(accounts.len() - 4);
+    accounts.truncate(accounts.len() - 4);
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(&instruction, &accounts);
+    assert!(matches!(result.program_result, ProgramResult::Failure(_)));
+}
+
+#[test]
+fn take_native_a() {
+    // Add our built program binary
+    let mut mollusk: Mollusk = Mollusk::new(&crate::ID, "target/deploy/native_escrow_2024");
+
+    // Set our seed
+    let seed: u64 = 1337;
+
+    // Programs
+    mollusk.add_program(&spl_token::ID, "src/tests/spl_token-3.5.0");
+    let (token_program, token_program_account) = (spl_token::ID, program_account(&spl_token::ID));
+    let (system_program, system_program_account) = program::system_program();
+
+    // Accounts
+    let taker = Pubkey::new_from_array([0x04; 32]);
+    let maker = Pubkey::new_from_array([0x01; 32]);
+    let mint_a = spl_token::native_mint::ID;
+    let mint_b = Pubkey::new_from_array([0x03; 32]);
+    let treasury = Pubkey::new_from_array([0x08; 32]);
+    let taker_ta_b = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &taker,
+        &mint_b,
+        &token_program,
+    );
+    let maker_ta_b = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &maker,
+        &mint_b,
+        &token_program,
+    );
+    let (escrow, bump) = Pubkey::find_program_address(
+        &[b"escrow", maker.as_ref(), &seed.to_le_bytes()],
+        &crate::ID,
+    );
+    let vault = Pubkey::find_program_address(&[b"vault", escrow.as_ref()], &crate::ID).0;
+
+    // Escrow holding SOL on top of its rent
+    let mut escrow_account = escrow_account(
+        &mollusk,
+        Escrow {
+            seed,
+            maker,
+            mint_a,
+            mint_b,
+            receive: 200_000,
+            flags: FLAG_NATIVE_A,
+            bump,
+            ..Default::default()
+        },
+    );
+    escrow_account.set_lamports(escrow_account.lamports() + 100_000);
+
+    // Fill a quarter of the SOL, paid to the taker's wallet in place of a token account
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &take_partial_instruction(
+            &taker,
+            &maker,
+            &mint_a,
+            &mint_b,
+            &taker,
+            &taker_ta_b,
+            &maker_ta_b,
+            &treasury,
+            &token_program,
+            seed,
+            25_000,
+        ),
+        &vec![
+            (
+                taker,
+                AccountSharedData::new(1_000_000_000, 0, &Pubkey::default()),
+            ),
+            (
+                maker,
+                AccountSharedData::new(1_000_000_000, 0, &Pubkey::default()),
+            ),
+            (mint_a, AccountSharedData::default()),
+            (mint_b, mint_account(&mollusk, Pubkey::new_from_array([0x06; 32]))),
+            (taker_ta_b, token_account(&mollusk, mint_b, taker, 1_000_000_000)),
+            (maker_ta_b, token_account(&mollusk, mint_b, maker, 0)),
+            (escrow, escrow_account),
+            (vault, AccountSharedData::default()),
+            (token_program, token_program_account),
+            (system_program, system_program_account),
+            (CONFIG_ADDRESS, AccountSharedData::default()),
+            (treasury, AccountSharedData::default()),
+        ],
+    );
+    assert!(matches!(result.program_result, ProgramResult::Success));
+    assert_eq!(resulting_account(&result, &taker).lamports(), 1_000_025_000);
+    assert_eq!(
+        resulting_account(&result, &escrow).lamports(),
+        mollusk
+            .sysvars
+            .rent
+            .minimum_balance(mem::size_of::<Escrow>())
+            + 75_000
+    );
+
+    // Fill the rest, closing the escrow without a vault to close
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &take_instruction(
+            &taker,
+            &maker,
+            &mint_a,
+            &mint_b,
+            &taker,
+            &taker_ta_b,
+            &maker_ta_b,
+            &treasury,
+            &token_program,
+            seed,
+        ),
+        &result.resulting_accounts,
+    );
+    assert!(matches!(result.program_result, ProgramResult::Success));
+    assert_eq!(resulting_account(&result, &taker).lamports(), 1_000_100_000);
+    assert_eq!(resulting_account(&result, &escrow).lamports(), 0);
+    assert_eq!(
+        spl_token::state::Account::unpack(resulting_account(&result, &maker_ta_b).data())
+            .unwrap()
+            .amount,
+        200_000
+    );
+}
+
+#[test]
+fn take_native_b() {
+    // Add our built program binary
+    let mut mollusk: Mollusk = Mollusk::new(&crate::ID, "target/deploy/native_escrow_2024");
+
+    // Set our seed
+    let seed: u64 = 1337;
+
+    // Programs
+    mollusk.add_program(&spl_token::ID, "src/tests/spl_token-3.5.0");
+    let (token_program, token_program_account) = (spl_token::ID, program_account(&spl_token::ID));
+    let (system_program, system_program_account) = program::system_program();
+
+    // Accounts
+    let taker = Pubkey::new_from_array([0x04; 32]);
+    let maker = Pubkey::new_from_array([0x01; 32]);
+    let mint_a = Pubkey::new_from_array([0x02; 32]);
+    let mint_b = spl_token::native_mint::ID;
+    let treasury = Pubkey::new_from_array([0x08; 32]);
+    let taker_ta_a = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &taker,
+        &mint_a,
+        &token_program,
+    );
+    let (escrow, bump) = Pubkey::find_program_address(
+        &[b"escrow", maker.as_ref(), &seed.to_le_bytes()],
+        &crate::ID,
+    );
+    let (vault, vault_bump) =
+        Pubkey::find_program_address(&[b"vault", escrow.as_ref()], &crate::ID);
+
+    // Fill a quarter of the vault, paying SOL to the maker's wallet in place of a token account
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &take_partial_instruction(
+            &taker,
+            &maker,
+            &mint_a,
+            &mint_b,
+            &taker_ta_a,
+            &taker,
+            &maker,
+            &treasury,
+            &token_program,
+            seed,
+            25_000,
+        ),
+        &vec![
+            (
+                taker,
+                AccountSharedData::new(1_000_000_000, 0, &Pubkey::default()),
+            ),
+            (
+                maker,
+                AccountSharedData::new(1_000_000_000, 0, &Pubkey::default()),
+            ),
+            (mint_a, mint_account(&mollusk, Pubkey::new_from_array([0x05; 32]))),
+            (mint_b, AccountSharedData::default()),
+            (taker_ta_a, token_account(&mollusk, mint_a, taker, 0)),
+            (
+                escrow,
+                escrow_account(
+                    &mollusk,
+                    Escrow {
+                        seed,
+                        maker,
+                        mint_a,
+                        mint_b,
+                        receive: 200_000,
+                        flags: FLAG_NATIVE_B,
+                        bump,
+                        vault_bump,
+                        ..Default::default()
+                    },
+                ),
+            ),
+            (vault, token_account(&mollusk, mint_a, escrow, 100_000)),
+            (token_program, token_program_account),
+            (system_program, system_program_account),
+            (CONFIG_ADDRESS, AccountSharedData::default()),
+            (treasury, AccountSharedData::default()),
+        ],
+    );
+    assert!(matches!(result.program_result, ProgramResult::Success));
+    assert_eq!(resulting_account(&result, &taker).lamports(), 1_000_000_000 - 50_000);
+    assert_eq!(resulting_account(&result, &maker).lamports(), 1_000_000_000 + 50_000);
+
+    // Fill the rest, closing the vault and escrow
+    let result: mollusk_svm::result::InstructionResult = mollusk.process_instruction(
+        &take_instruction(
+            &taker,
+            &maker,
+            &mint_a,
+            &mint_b,
+            &taker_ta_a,
+            &taker,
+            &maker,
+            &treasury,
+            &token_program,
+            seed,
+        ),
+        &result.resulting_accounts,
+    );
+    assert!(matches!(result.program_result, ProgramResult::Success));
+    assert_eq!(resulting_account(&result, &taker).lamports(), 1_000_000_000 - 200_000);
+    assert_eq!(resulting_account(&result, &vault).lamports(), 0);
+    assert_eq!(resulting_account(&result, &escrow).lamports(), 0);
+    assert_eq!(
+        spl_token::state::Account::unpack(resulting_account(&result, &taker_ta_a).data())
+            .unwrap()
+            .amount,
+        100_000
+    );
+}
+
+#[test]
+fn stored_bumps_save_compute_units() {
+    // Add our built program binary
+    let mut mollusk: Mollusk = Mollusk::new(&crate::ID, "target/deploy/native_escrow_2024");
+
+    // Programs
+    mollusk.add_program(&spl_token::ID, "src/tests/spl_token-3.5.0");
+    let (token_program, token_program_account) = (spl_token::ID, program_account(&spl_token::ID));
+    let (system_program, system_program_account) = program::system_program();
+
+    // Accounts
+    let maker = Pubkey::new_from_array([0x01; 32]);
+    let mint_a = Pubkey::new_from_array([0x02; 32]);
+    let mint_b = Pubkey::new_from_array([0x03; 32]);
+    let maker_ta_a = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &maker,
+        &mint_a,
+        &token_program,
+    );
+
+    // Find a seed whose escrow and vault bumps both satisfy `is_bump`
+    let find_seed = |is_bump: fn(u8) -> bool| {
+        (0u64..)
+            .find_map(|seed| {
+                let (escrow, bump) = Pubkey::find_program_address(
+                    &[b"escrow", maker.as_ref(), &seed.to_le_bytes()],
+                    &crate::ID,
+                );
+                let (vault, vault_bump) =
+                    Pubkey::find_program_address(&[b"vault", escrow.as_ref()], &crate::ID);
+                (is_bump(bump) && is_bump(vault_bump)).then_some((seed, escrow, bump, vault, vault_bump))
+            })
+            .unwrap()
+    };
+
+    // Refund an escrow in the current layout, with its bumps stored
+    let refund = |(seed, escrow, bump, vault, vault_bump): (u64, Pubkey, u8, Pubkey, u8)| {
+        mollusk.process_instruction(
+            &refund_instruction(&maker, &mint_a, &maker_ta_a, &token_program, seed),
+            &vec![
+                (
+                    maker,
+                    AccountSharedData::new(1_000_000_000, 0, &Pubkey::default()),
+                ),
+                (mint_a, mint_account(&mollusk, Pubkey::new_from_array([0x05; 32]))),
+                (maker_ta_a, token_account(&mollusk, mint_a, maker, 0)),
+                (
+                    escrow,
+                    escrow_account(
+                        &mollusk,
+                        Escrow {
+                            seed,
+                            maker,
+                            mint_a,
+                            mint_b,
+                            receive: 100_000,
+                            bump,
+                            vault_bump,
+                            ..Default::default()
+                        },
+                    ),
+                ),
+                (vault, token_account(&mollusk, mint_a, escrow, 100_000)),
+                (token_program, token_program_account.clone()),
+                (system_program, system_program_account.clone()),
+            ],
+        )
+    };
+
+    // A bump search costs one create_program_address per bump tried, from 255 down, so
+    // escrows whose bumps are found first try and after several tries only cost the same with stored bumps
+    let result: mollusk_svm::result::InstructionResult = refund(find_seed(|bump| bump == 255));
+    let low_bump_result: mollusk_svm::result::InstructionResult = refund(find_seed(|bump| bump <= 250));
+    assert!(matches!(result.program_result, ProgramResult::Success));
+    assert!(matches!(low_bump_result.program_result, ProgramResult::Success));
+    assert_eq!(result.compute_units_consumed, low_bump_result.compute_units_consumed);
+}
+
+fn mint_account(mollusk: &Mollusk, mint_authority: Pubkey) -> AccountSharedData {
+    let mut account = AccountSharedData::new(
+        mollusk
+            .sysvars
+            .rent
+            .minimum_balance(spl_token::state::Mint::LEN),
+        spl_token::state::Mint::LEN,
+        &spl_token::ID,
+    );
+    solana_program::program_pack::Pack::pack(
+        spl_token::state::Mint {
+            mint_authority: COption::Some(mint_authority),
+            supply: 100_000_000_000,
+            decimals: 6,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        },
+        account.data_as_mut_slice(),
+    )
+    .unwrap();
+    account
+}
+
+fn token_account(mollusk: &Mollusk, mint: Pubkey, owner: Pubkey, amount: u64) -> AccountSharedData {
+    let mut account = AccountSharedData::new(
+        mollusk
+            .sysvars
+            .rent
+            .minimum_balance(spl_token::state::Account::LEN),
+        spl_token::state::Account::LEN,
+        &spl_token::ID,
+    );
+    solana_program::program_pack::Pack::pack(
+        spl_token::state::Account {
+            mint,
+            owner,
+            amount,
+            delegate: COption::None,
+            state: AccountState::Initialized,
+            is_native: COption::None,
+            delegated_amount: 0,
+            close_authority: COption::None,
+        },
+        account.data_as_mut_slice(),
+    )
+    .unwrap();
+    account
+}
+
+fn mint_2022_account(
+    mollusk: &Mollusk,
+    mint_authority: Pubkey,
+    extension_types: &[ExtensionType],
+    init_extensions: impl FnOnce(&mut StateWithExtensionsMut<spl_token_2022::state::Mint>),
+) -> AccountSharedData {
+    let space =
+        ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(extension_types).unwrap();
+    let mut account = AccountSharedData::new(
+        mollusk.sysvars.rent.minimum_balance(space),
+        space,
+        &spl_token_2022::ID,
+    );
+    let mut mint =
+        StateWithExtensionsMut::<spl_token_2022::state::Mint>::unpack_uninitialized(account.data_as_mut_slice())
+            .unwrap();
+    init_extensions(&mut mint);
+    mint.base = spl_token_2022::state::Mint {
+        mint_authority: COption::Some(mint_authority),
+        supply: 100_000_000_000,
+        decimals: 6,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    };
+    mint.pack_base();
+    mint.init_account_type().unwrap();
+    account
+}
+
+fn token_2022_account(
+    mollusk: &Mollusk,
+    mint_extension_types: &[ExtensionType],
+    mint: Pubkey,
+    owner: Pubkey,
+    amount: u64,
+) -> AccountSharedData {
+    // Carry the extensions required by the mint, plus an immutable owner
+    let mut extension_types = ExtensionType::get_required_init_account_extensions(mint_extension_types);
+    extension_types.push(ExtensionType::ImmutableOwner);
+    let space =
+        ExtensionType::try_calculate_account_len::<spl_token_2022::state::Account>(&extension_types).unwrap();
+    let mut account = AccountSharedData::new(
+        mollusk.sysvars.rent.minimum_balance(space),
+        space,
+        &spl_token_2022::ID,
+    );
+    let mut token_account =
+        StateWithExtensionsMut::<spl_token_2022::state::Account>::unpack_uninitialized(account.data_as_mut_slice())
+            .unwrap();
+    for extension_type in extension_types {
+        match extension_type {
+            ExtensionType::ImmutableOwner => {
+                token_account.init_extension::<ImmutableOwner>(true).unwrap();
+            }
+            ExtensionType::TransferFeeAmount => {
+                token_account.init_extension::<TransferFeeAmount>(true).unwrap();
+            }
+            ExtensionType::TransferHookAccount => {
+                token_account.init_extension::<TransferHookAccount>(true).unwrap();
+            }
+            _ => unreachable!("unsupported account extension {:?}", extension_type),
+        }
+    }
+    token_account.base = spl_token_2022::state::Account {
+        mint,
+        owner,
+        amount,
+        state: spl_token_2022::state::AccountState::Initialized,
+        ..Default::default()
+    };
+    token_account.pack_base();
+    token_account.init_account_type().unwrap();
+    account
+}
+
+fn token_2022_amount(result: &mollusk_svm::result::InstructionResult, address: &Pubkey) -> u64 {
+    StateWithExtensions::<spl_token_2022::state::Account>::unpack(resulting_account(result, address).data())
+        .unwrap()
+        .base
+        .amount
+}
+
+fn escrow_account(mollusk: &Mollusk, escrow: Escrow) -> AccountSharedData {
+    let mut account = AccountSharedData::new(
+        mollusk
+            .sysvars
+            .rent
+            .minimum_balance(mem::size_of::<Escrow>()),
+        mem::size_of::<Escrow>(),
+        &crate::ID,
+    );
+    account.set_data_from_slice(bytes_of::<Escrow>(&escrow));
+    account
+}
+
+fn config_account(mollusk: &Mollusk, config: Config) -> AccountSharedData {
+    let mut account = AccountSharedData::new(
+        mollusk
+            .sysvars
+            .rent
+            .minimum_balance(mem::size_of::<Config>()),
+        mem::size_of::<Config>(),
+        &crate::ID,
+    );
+    account.set_data_from_slice(bytes_of::<Config>(&config));
+    account
+}
 
-    assert!(matches!(result.program_result, ProgramResult::Success))
+fn resulting_account<'a>(
+    result: &'a mollusk_svm::result::InstructionResult,
+    address: &Pubkey,
+) -> &'a AccountSharedData {
+    &result
+        .resulting_accounts
+        .iter()
+        .find(|(key, _)| key == address)
+        .unwrap()
+        .1
 }
@@ -0,0 +1,49 @@
+use solana_program::{
+    account_info::AccountInfo,
+    clock::Clock,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    sysvar::Sysvar,
+};
+use crate::{error::EscrowError, Config, Escrow, MintEntry, Update, UPDATE_FLAG_SET_EXPIRY};
+
+/// Maker changes the price, mint B and expiry of an open escrow in place
+pub fn process(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult {
+    let Update {
+        receive,
+        expires_at,
+        flags,
+        ..
+    } = Update::try_from(data)?;
+
+    // Leave the expiry untouched unless asked to replace it
+    if flags & !UPDATE_FLAG_SET_EXPIRY != 0 {
+        return Err(EscrowError::InvalidFlags.into());
+    }
+    let expires_at = (flags & UPDATE_FLAG_SET_EXPIRY != 0).then_some(expires_at);
+
+    let [maker, mint_b, escrow, config, mint_b_entry] = accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Make sure the maker is a signer
+    if !maker.is_signer {
+        return Err(EscrowError::MissingSigner.into());
+    }
+
+    // Check & Get escrow account data
+    let (escrow_data, _) = Escrow::get_data_and_bump(maker.key, escrow)?;
+
+    // Make sure the escrow is not frozen by a dispute
+    escrow_data.check_dispute()?;
+
+    // Make sure an arbitrated escrow has expired before the maker acts alone
+    escrow_data.check_arbiter(Clock::get()?.unix_timestamp)?;
+
+    // Make sure mint B may be listed
+    MintEntry::check_listable(Config::load(config)?, mint_b.key, mint_b_entry)?;
+
+    // Update: Set receive, mint B & expiry, if given
+    Escrow::update(escrow_data, receive, expires_at, mint_b.key, escrow)
+}
@@ -0,0 +1,31 @@
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+};
+use crate::{Config, UpdateConfig};
+
+/// Admin changes the protocol fee, treasury and pause switch, or nominates a new admin
+pub fn process(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult {
+    let UpdateConfig {
+        fee_bps,
+        flags,
+        treasury,
+        pending_admin,
+        ..
+    } = UpdateConfig::try_from(data)?;
+
+    let [admin, config] = accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Check & Get config account data
+    let config_data = Config::get(config)?;
+
+    // Make sure the admin signed
+    config_data.check_admin(admin)?;
+
+    // Update: Set fee, flags, treasury & pending admin
+    Config::update(config_data, fee_bps, flags, &treasury, &pending_admin, config)
+}
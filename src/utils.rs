@@ -1,16 +1,38 @@
 use solana_program::{
+    account_info::AccountInfo,
+    bpf_loader_upgradeable,
+    clock::Clock,
+    entrypoint::ProgramResult,
+    program::invoke_signed,
     program_error::ProgramError,
     pubkey::Pubkey,
+    sysvar::Sysvar,
 };
+use spl_token_2022::{
+    extension::{
+        transfer_fee::{instruction::transfer_checked_with_fee, TransferFeeAmount, TransferFeeConfig},
+        transfer_hook, BaseStateWithExtensions, StateWithExtensions,
+    },
+    generic_token_account::GenericTokenAccount,
+    instruction::transfer_checked,
+    state::{Account, Mint},
+};
+use spl_transfer_hook_interface::onchain::add_extra_accounts_for_execute_cpi;
+use crate::error::EscrowError;
 
 #[inline]
-pub fn check_eq_program_derived_address(
+pub fn check_eq_program_address(
     seeds: &[&[u8]],
     program_id: &Pubkey,
     address: &Pubkey,
+    error: EscrowError,
 ) -> Result<(), ProgramError> {
-    let (derived_address, _) = Pubkey::try_find_program_address(seeds, program_id).ok_or(ProgramError::InvalidAccountData)?;
-    Ok(assert!(derived_address.eq(address)))
+    // Seeds include the bump, so this skips the bump search of find_program_address
+    let derived_address = Pubkey::create_program_address(seeds, program_id).map_err(|_| error)?;
+    if derived_address.ne(address) {
+        return Err(error.into());
+    }
+    Ok(())
 }
 
 #[inline]
@@ -18,8 +40,187 @@ pub fn check_eq_program_derived_address_and_get_bump(
     seeds: &[&[u8]],
     program_id: &Pubkey,
     address: &Pubkey,
+    error: EscrowError,
 ) -> Result<u8, ProgramError> {
-    let (derived_address, bump) = Pubkey::try_find_program_address(seeds, program_id).ok_or(ProgramError::InvalidAccountData)?;
-    assert!(derived_address.eq(address));
+    let (derived_address, bump) = Pubkey::try_find_program_address(seeds, program_id).ok_or(error)?;
+    if derived_address.ne(address) {
+        return Err(error.into());
+    }
     Ok(bump)
-}
\ No newline at end of file
+}
+
+#[inline]
+pub fn check_token_program(token_program: &Pubkey) -> Result<(), ProgramError> {
+    // By checking this, we know our token accounts are correct by virtue of Token Program checking them
+    if ![spl_token::ID, spl_token_2022::ID].contains(token_program) {
+        return Err(EscrowError::UnsupportedTokenProgram.into());
+    }
+    Ok(())
+}
+
+#[inline]
+pub fn pro_rata(value: u64, amount: u64, total: u64) -> Result<u64, ProgramError> {
+    if total == 0 {
+        return Err(ProgramError::ArithmeticOverflow);
+    }
+
+    // Scale value by amount / total, rounded up in favour of the maker
+    u64::try_from((value as u128 * amount as u128).div_ceil(total as u128))
+        .map_err(|_| ProgramError::ArithmeticOverflow)
+}
+
+#[inline]
+pub fn check_upgrade_authority(program_data: &AccountInfo, authority: &Pubkey) -> Result<(), ProgramError> {
+    // Check program data account of this program
+    let (program_data_address, _) = Pubkey::find_program_address(&[crate::ID.as_ref()], &bpf_loader_upgradeable::ID);
+    if program_data.key.ne(&program_data_address) || program_data.owner.ne(&bpf_loader_upgradeable::ID) {
+        return Err(EscrowError::InvalidAuthority.into());
+    }
+
+    // ProgramData is laid out as a u32 tag of 3, a u64 slot and an optional upgrade authority
+    let data = program_data.try_borrow_data()?;
+    match data.get(..45) {
+        Some([3, 0, 0, 0, _, _, _, _, _, _, _, _, 1, upgrade_authority @ ..]) if upgrade_authority.eq(authority.as_ref()) => Ok(()),
+        _ => Err(EscrowError::InvalidAuthority.into()),
+    }
+}
+
+#[inline]
+pub fn get_mint_decimals(mint: &AccountInfo) -> Result<u8, ProgramError> {
+    // Works for both SPL Token and Token-2022 mints, with or without extensions
+    Ok(StateWithExtensions::<Mint>::unpack(&mint.try_borrow_data()?)?.base.decimals)
+}
+
+#[inline]
+pub fn get_token_account_amount(token_account: &AccountInfo) -> Result<u64, ProgramError> {
+    Ok(StateWithExtensions::<Account>::unpack(&token_account.try_borrow_data()?)?.base.amount)
+}
+
+#[inline]
+pub fn get_token_account_owner(token_account: &AccountInfo) -> Result<Pubkey, ProgramError> {
+    Account::unpack_account_owner(&token_account.try_borrow_data()?)
+        .copied()
+        .ok_or(ProgramError::InvalidAccountData)
+}
+
+#[inline]
+pub fn get_withheld_amount(token_account: &AccountInfo) -> Result<u64, ProgramError> {
+    // Transfer fees are withheld in the receiving account until harvested to the mint
+    let token_account_data = token_account.try_borrow_data()?;
+    let token_account = StateWithExtensions::<Account>::unpack(&token_account_data)?;
+    Ok(token_account
+        .get_extension::<TransferFeeAmount>()
+        .map_or(0, |transfer_fee_amount| transfer_fee_amount.withheld_amount.into()))
+}
+
+#[inline]
+pub fn get_transfer_fee(mint: &AccountInfo, amount: u64) -> Result<Option<u64>, ProgramError> {
+    // Only Token-2022 mints with the TransferFeeConfig extension charge a fee
+    let mint_data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<Mint>::unpack(&mint_data)?;
+    let Ok(transfer_fee_config) = mint.get_extension::<TransferFeeConfig>() else {
+        return Ok(None);
+    };
+
+    transfer_fee_config
+        .calculate_epoch_fee(Clock::get()?.epoch, amount)
+        .map(Some)
+        .ok_or(ProgramError::ArithmeticOverflow)
+}
+
+#[inline]
+pub fn get_pre_fee_amount(mint: &AccountInfo, post_fee_amount: u64) -> Result<u64, ProgramError> {
+    // Amount to send so that `post_fee_amount` arrives after the transfer fee
+    let mint_data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<Mint>::unpack(&mint_data)?;
+    let Ok(transfer_fee_config) = mint.get_extension::<TransferFeeConfig>() else {
+        return Ok(post_fee_amount);
+    };
+
+    transfer_fee_config
+        .get_epoch_fee(Clock::get()?.epoch)
+        .calculate_pre_fee_amount(post_fee_amount)
+        .ok_or(ProgramError::ArithmeticOverflow)
+}
+
+#[inline]
+pub fn close_program_account(
+    destination: &AccountInfo,
+    account: &AccountInfo,
+) -> ProgramResult {
+    // Close the account, sending its lamports to the destination
+    let balance = account.lamports();
+    account.realloc(0, false)?;
+    **account.lamports.borrow_mut() = 0;
+    **destination.lamports.borrow_mut() += balance;
+    account.assign(&Pubkey::default());
+
+    Ok(())
+}
+
+#[inline]
+#[allow(clippy::too_many_arguments)]
+pub fn transfer<'a>(
+    token_program: &Pubkey,
+    source: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    destination: &AccountInfo<'a>,
+    authority: &AccountInfo<'a>,
+    amount: u64,
+    signers_seeds: &[&[&[u8]]],
+    remaining_accounts: &[AccountInfo<'a>],
+) -> ProgramResult {
+    // Get token decimals
+    let decimals = get_mint_decimals(mint)?;
+
+    // Pass the expected epoch fee along for mints with a transfer fee
+    let mut instruction = match get_transfer_fee(mint, amount)? {
+        Some(fee) => transfer_checked_with_fee(
+            token_program,
+            source.key,
+            mint.key,
+            destination.key,
+            authority.key,
+            &[],
+            amount,
+            decimals,
+            fee,
+        )?,
+        None => transfer_checked(
+            token_program,
+            source.key,
+            mint.key,
+            destination.key,
+            authority.key,
+            &[],
+            amount,
+            decimals,
+        )?,
+    };
+
+    let mut account_infos = vec![
+        source.clone(),
+        mint.clone(),
+        destination.clone(),
+        authority.clone(),
+    ];
+
+    // Forward the extra accounts required by the mint's transfer hook, if any
+    let transfer_hook_program_id =
+        transfer_hook::get_program_id(&StateWithExtensions::<Mint>::unpack(&mint.try_borrow_data()?)?);
+    if let Some(program_id) = transfer_hook_program_id {
+        add_extra_accounts_for_execute_cpi(
+            &mut instruction,
+            &mut account_infos,
+            &program_id,
+            source.clone(),
+            mint.clone(),
+            destination.clone(),
+            authority.clone(),
+            amount,
+            remaining_accounts,
+        )?;
+    }
+
+    invoke_signed(&instruction, &account_infos, signers_seeds)
+}
@@ -0,0 +1,36 @@
+use solana_program::{
+    account_info::AccountInfo,
+    clock::Clock,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    sysvar::Sysvar,
+};
+use crate::{error::EscrowError, Escrow, Withdraw};
+
+/// Maker removes part of the funds in the vault of an open escrow
+pub fn process(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult {
+    let Withdraw { amount } = Withdraw::try_from(data)?;
+
+    let [maker, mint_a, maker_ta_a, escrow, vault, token_program, _system_program, remaining_accounts @ ..] =
+        accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Make sure the maker is a signer
+    if !maker.is_signer {
+        return Err(EscrowError::MissingSigner.into());
+    }
+
+    // Check & Get escrow account data and bump
+    let (escrow_data, bump) = Escrow::get_data_and_bump(maker.key, escrow)?;
+
+    // Make sure the escrow is not frozen by a dispute
+    escrow_data.check_dispute()?;
+
+    // Make sure an arbitrated escrow has expired before the maker acts alone
+    escrow_data.check_arbiter(Clock::get()?.unix_timestamp)?;
+
+    // Withdraw: Transfer funds out of the vault, Scale receive if priced proportionally
+    Escrow::withdraw(escrow_data, bump, token_program.key, amount, mint_a, maker, maker_ta_a, escrow, vault, remaining_accounts)
+}
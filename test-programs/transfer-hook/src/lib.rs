@@ -0,0 +1,45 @@
+//! Transfer hook loaded by the escrow tests. It approves a transfer only when
+//! every account listed in the mint's ExtraAccountMetaList was forwarded.
+//!
+//! Build it next to the escrow program with
+//! `cargo build-sbf --manifest-path test-programs/transfer-hook/Cargo.toml --sbf-out-dir target/deploy`.
+
+use solana_program::{
+    account_info::AccountInfo, entrypoint, entrypoint::ProgramResult, program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use spl_tlv_account_resolution::state::ExtraAccountMetaList;
+use spl_transfer_hook_interface::{
+    get_extra_account_metas_address,
+    instruction::{ExecuteInstruction, TransferHookInstruction},
+};
+
+entrypoint!(process_instruction);
+
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    // Only Execute is supported, the ExtraAccountMetaList is set up by the tests directly
+    let TransferHookInstruction::Execute { .. } = TransferHookInstruction::unpack(data)? else {
+        return Err(ProgramError::InvalidInstructionData);
+    };
+
+    let [_source, mint, _destination, _authority, extra_account_metas, ..] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Check the ExtraAccountMetaList belongs to the mint
+    if extra_account_metas.key.ne(&get_extra_account_metas_address(mint.key, program_id)) {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    // Check the extra accounts were forwarded in order
+    ExtraAccountMetaList::check_account_infos::<ExecuteInstruction>(
+        accounts,
+        data,
+        program_id,
+        &extra_account_metas.try_borrow_data()?,
+    )
+}